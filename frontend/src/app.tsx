@@ -1,30 +1,31 @@
 import {
     ChangeEvent,
-    Dispatch,
     FormEvent,
-    SetStateAction,
+    createElement,
     useEffect,
     useState,
 } from "react";
-import { solve24 } from "../pkg/twentyfour_wasm";
+import { solve, MathMl, Op, Solution } from "../pkg/twentyfour_wasm";
 import init from "../pkg/twentyfour_wasm_bg.wasm?init";
 import "./index.scss";
 
-interface Solution {
-    solution: string;
-    explanation: string[];
-}
-
 const MIN = 1;
 const MAX = 99;
 
+const MIN_CARDS = 2;
+// `solve` runs on the main thread, and six cards can take a second and give thousands of
+// solutions, while five take milliseconds
+const MAX_CARDS = 5;
+
+const CLASSIC_OPS: Op[] = ["add", "sub", "mul", "div"];
+const ALL_OPS: Op[] = [...CLASSIC_OPS, "pow"];
+
 const isValid = (n: number) => n >= MIN && n <= MAX;
 
 const App = () => {
-    const [n1, setN1] = useState(1);
-    const [n2, setN2] = useState(2);
-    const [n3, setN3] = useState(3);
-    const [n4, setN4] = useState(4);
+    const [nums, setNums] = useState([1, 2, 3, 4]);
+    const [exponents, setExponents] = useState(true);
+    const [integerOnly, setIntegerOnly] = useState(false);
     const [sols, setSols] = useState<Solution[]>([]);
     const [touched, setTouched] = useState(false);
 
@@ -32,24 +33,44 @@ const App = () => {
     const [inputsChangedSinceBtnClick, setInputsChangedSinceClick] =
         useState(true);
 
-    const inputsValid = [n1, n2, n3, n4].every((x) => isValid(x));
+    const inputsValid = nums.every((x) => isValid(x));
 
     useEffect(() => {
         init({});
     }, []);
 
     const handleNChange =
-        (setter: Dispatch<SetStateAction<number>>) =>
-        (e: ChangeEvent<HTMLInputElement>) => {
+        (i: number) => (e: ChangeEvent<HTMLInputElement>) => {
             if (e.target.validity.valid) {
                 setInputsChangedSinceClick(true);
-                setter(e.target.value as unknown as number);
+                const n = Number(e.target.value);
+                setNums(nums.map((m, j) => (i === j ? n : m)));
             }
         };
 
+    const handleCountChange = (e: ChangeEvent<HTMLSelectElement>) => {
+        const count = Number(e.target.value);
+        setInputsChangedSinceClick(true);
+        setNums(Array.from({ length: count }, (_, i) => nums[i] ?? i + 1));
+    };
+
+    const handleRulesChange = (withExponents: boolean) => () => {
+        setInputsChangedSinceClick(true);
+        setExponents(withExponents);
+    };
+
+    const handleIntegerOnlyChange = (e: ChangeEvent<HTMLInputElement>) => {
+        setInputsChangedSinceClick(true);
+        setIntegerOnly(e.target.checked);
+    };
+
     const handleBtnClick = () => {
         if (inputsChangedSinceBtnClick) {
-            const sols: Solution[] = JSON.parse(solve24(n1, n2, n3, n4));
+            // Classic rules leave out exponentiation
+            const sols = solve(nums, {
+                ops: exponents ? ALL_OPS : CLASSIC_OPS,
+                integerOnly,
+            });
             setTouched(true);
             setSols(sols);
         }
@@ -60,17 +81,61 @@ const App = () => {
         <main>
             <div>
                 <p>
-                    Enter four numbers below.
+                    Enter {nums.length} numbers below.
                     <br />
                     Click &ldquo;Solve&rdquo; to see every solution that equals
                     24.
                 </p>
 
+                <div id="rules">
+                    <label>
+                        Cards{" "}
+                        <select
+                            value={nums.length}
+                            onChange={handleCountChange}
+                        >
+                            {Array.from(
+                                { length: MAX_CARDS - MIN_CARDS + 1 },
+                                (_, i) => MIN_CARDS + i
+                            ).map((count) => (
+                                <option key={count} value={count}>
+                                    {count}
+                                </option>
+                            ))}
+                        </select>
+                    </label>
+                    <label>
+                        <input
+                            type="radio"
+                            name="rules"
+                            checked={!exponents}
+                            onChange={handleRulesChange(false)}
+                        />
+                        Classic rules
+                    </label>
+                    <label>
+                        <input
+                            type="radio"
+                            name="rules"
+                            checked={exponents}
+                            onChange={handleRulesChange(true)}
+                        />
+                        With exponents
+                    </label>
+                    <label>
+                        <input
+                            type="checkbox"
+                            checked={integerOnly}
+                            onChange={handleIntegerOnlyChange}
+                        />
+                        Integers only
+                    </label>
+                </div>
+
                 <div id="inputs">
-                    <Input value={n1} onChange={handleNChange(setN1)} />
-                    <Input value={n2} onChange={handleNChange(setN2)} />
-                    <Input value={n3} onChange={handleNChange(setN3)} />
-                    <Input value={n4} onChange={handleNChange(setN4)} />
+                    {nums.map((n, i) => (
+                        <Input key={i} value={n} onChange={handleNChange(i)} />
+                    ))}
                     <button
                         onClick={handleBtnClick}
                         disabled={!inputsValid}
@@ -105,17 +170,17 @@ const App = () => {
                             ? sols.map((sol, i) => (
                                   <tr key={i}>
                                       <td className="num-col">{i + 1}</td>
-                                      <td className="sol-col">
-                                          {sol.solution} = 24
+                                      <td
+                                          className="sol-col"
+                                          aria-label={`${sol.solution} = 24`}
+                                      >
+                                          <Formula node={sol.mathml} /> = 24
                                       </td>
                                       <td className="exp-col">
                                           {sol.explanation.map((x, j) => (
-                                              <div
-                                                  key={j}
-                                                  dangerouslySetInnerHTML={{
-                                                      __html: x,
-                                                  }}
-                                              />
+                                              <div key={j}>
+                                                  <Formula node={x} />
+                                              </div>
                                           ))}
                                       </td>
                                   </tr>
@@ -162,3 +227,20 @@ const Input = ({
         />
     );
 };
+
+// Builds the MathML elements from the tree that `solve` returns
+const Formula = ({ node }: { node: MathMl }) => {
+    const [[tag, content]] = Object.entries(node) as [
+        string,
+        string | MathMl | MathMl[]
+    ][];
+    if (typeof content === "string") {
+        return createElement(tag, null, content);
+    }
+    const children = Array.isArray(content) ? content : [content];
+    return createElement(
+        tag,
+        null,
+        children.map((child, i) => <Formula key={i} node={child} />)
+    );
+};
@@ -0,0 +1,351 @@
+use clap::{error::ErrorKind, CommandFactory, Parser, ValueEnum};
+use itertools::Itertools;
+use serde_json::json;
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+use twentyfour::{solve_batch, BoundOp, Cards, Ops, Solutions, SolveError, Style, TARGET};
+
+/// Solves the 24 card game
+///
+/// Cards are given as arguments, or else read from standard input with one hand per line.
+/// To check many hands at once, `--batch` reads them from a file and summarizes each.
+#[derive(Debug, Parser)]
+#[command(name = "twentyfour", version)]
+struct Args {
+    /// The cards of a hand, such as `3 3 8 8`
+    cards: Vec<u8>,
+    /// The value that solutions must equal
+    #[arg(long, default_value_t = TARGET)]
+    target: f64,
+    /// The permitted operations, such as `+-*/` to leave out exponentiation
+    #[arg(long, default_value = "+-*/^")]
+    ops: Ops,
+    /// Only print the first solution found
+    #[arg(long, conflicts_with = "count")]
+    first: bool,
+    /// Only print how many solutions there are
+    #[arg(long)]
+    count: bool,
+    /// How solutions are printed
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// Read hands from a CSV or text file with one hand per line, and print whether each is
+    /// solvable, how many solutions it has and one of them
+    #[arg(long, conflicts_with_all = ["cards", "first", "count"])]
+    batch: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    /// Each solution followed by its steps
+    Text,
+    /// One JSON object per hand
+    Json,
+    /// An `align*` environment per solution, with its steps
+    Latex,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    if !args.target.is_finite() {
+        Args::command()
+            .error(
+                ErrorKind::ValueValidation,
+                "the target must be a finite number",
+            )
+            .exit()
+    }
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        // Stop quietly when the output is closed early, such as by `head`
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Prints the solutions of every hand, returning whether every line of input was a valid hand
+fn run(args: &Args) -> Result<bool, io::Error> {
+    let mut out = io::stdout().lock();
+    if let Some(path) = &args.batch {
+        return run_batch(args, path, &mut out);
+    }
+    if !args.cards.is_empty() {
+        print_hand(args, args.cards.clone(), false, &mut out)?;
+        return Ok(true);
+    }
+
+    let mut valid = true;
+    for (i, line) in io::stdin().lock().lines().enumerate() {
+        let line = line?;
+        match parse_hand(&line) {
+            Ok(cards) if cards.is_empty() => {}
+            Ok(cards) => print_hand(args, cards, true, &mut out)?,
+            Err(e) => {
+                eprintln!("line {}: {e}", i + 1);
+                valid = false;
+            }
+        }
+    }
+    Ok(valid)
+}
+
+/// Summarizes every hand in the file at `path`, returning whether every line was a valid hand.
+///
+/// A first line that is not a hand is taken to be the header of a CSV file.
+fn run_batch(args: &Args, path: &Path, out: &mut impl Write) -> Result<bool, io::Error> {
+    let input = fs::read_to_string(path)?;
+    let mut valid = true;
+    let mut hands = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        match parse_hand(line) {
+            Ok(cards) if cards.is_empty() => {}
+            Ok(cards) => hands.push(cards),
+            Err(_) if i == 0 => {}
+            Err(e) => {
+                eprintln!("line {}: {e}", i + 1);
+                valid = false;
+            }
+        }
+    }
+
+    let solvers = hands.iter().map(|cards| solver(args, cards.clone()));
+    if args.format == Format::Latex {
+        writeln!(out, "\\begin{{tabular}}{{lrl}}")?;
+        writeln!(out, "Cards & Solutions & Example \\\\")?;
+    }
+    for (cards, sols) in hands.iter().zip(solve_batch(solvers)) {
+        print_summary(args, cards, &sols.map_err(invalid_input)?, out)?;
+    }
+    if args.format == Format::Latex {
+        writeln!(out, "\\end{{tabular}}")?;
+    }
+    Ok(valid)
+}
+
+/// Prints whether a hand is solvable, how many solutions it has and the first of them
+fn print_summary(
+    args: &Args,
+    cards: &[u8],
+    sols: &Solutions,
+    out: &mut impl Write,
+) -> Result<(), io::Error> {
+    let label = cards.iter().join(" ");
+    let example = sols.0.first();
+    match (args.format, example) {
+        (Format::Text, Some(example)) => {
+            let plural = if sols.len() == 1 { "" } else { "s" };
+            writeln!(
+                out,
+                "{label}: solvable, {} solution{plural}, {}",
+                sols.len(),
+                example.format(Style::new())
+            )
+        }
+        (Format::Text, None) => writeln!(out, "{label}: unsolvable"),
+        (Format::Json, _) => writeln!(
+            out,
+            "{}",
+            json!({
+                "cards": cards,
+                "solvable": example.is_some(),
+                "solutions": sols.len(),
+                "example": example.map(|sol| sol.format(Style::new())),
+            })
+        ),
+        (Format::Latex, _) => writeln!(
+            out,
+            "{label} & {} & {} \\\\",
+            sols.len(),
+            example.map_or(String::new(), |sol| format!("${}$", sol.to_latex()))
+        ),
+    }
+}
+
+/// Reads the cards of a line separated by whitespace or commas
+fn parse_hand(line: &str) -> Result<Vec<u8>, String> {
+    line.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().map_err(|_| format!("invalid card `{s}`")))
+        .collect()
+}
+
+/// Prints the solutions of one hand in the format of `args`.
+///
+/// Text output names the hand first when `labelled`, since many hands are printed together.
+fn print_hand(
+    args: &Args,
+    cards: Vec<u8>,
+    labelled: bool,
+    out: &mut impl Write,
+) -> Result<(), io::Error> {
+    let label = cards.iter().join(" ");
+    let solver = solver(args, cards.clone());
+    // Solutions and their steps are written alike
+    let style = Style::new();
+
+    if args.count {
+        let count = solver.solve().map_err(invalid_input)?.len();
+        return match args.format {
+            Format::Json => writeln!(out, "{}", json!({ "cards": cards, "count": count })),
+            Format::Text | Format::Latex if labelled => writeln!(out, "{label}: {count}"),
+            Format::Text | Format::Latex => writeln!(out, "{count}"),
+        };
+    }
+
+    let sols = if args.first {
+        solver
+            .first_solution()
+            .map_err(invalid_input)?
+            .into_iter()
+            .collect()
+    } else {
+        solver.solve().map_err(invalid_input)?.0
+    };
+    match args.format {
+        Format::Text => {
+            if labelled {
+                writeln!(out, "{label}")?;
+            }
+            if sols.is_empty() {
+                writeln!(out, "No solutions")?;
+            }
+            for sol in &sols {
+                writeln!(out, "{}", sol.format(style))?;
+                for step in sol.explain() {
+                    writeln!(out, "    {}", step.format(style))?;
+                }
+            }
+            if labelled {
+                writeln!(out)?;
+            }
+            Ok(())
+        }
+        Format::Json => {
+            let sols = sols
+                .iter()
+                .map(|sol| {
+                    let steps = sol
+                        .explain()
+                        .iter()
+                        .map(|step| step.format(style))
+                        .collect::<Vec<_>>();
+                    json!({ "solution": sol.format(style), "steps": steps })
+                })
+                .collect::<Vec<_>>();
+            writeln!(out, "{}", json!({ "cards": cards, "solutions": sols }))
+        }
+        Format::Latex => {
+            writeln!(out, "% {label}")?;
+            if sols.is_empty() {
+                writeln!(out, "% No solutions")?;
+            }
+            for sol in &sols {
+                print_latex(sol, args.target, out)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn solver(args: &Args, cards: Vec<u8>) -> Cards {
+    Cards::with_ops(cards, args.ops.clone())
+        .with_target(args.target)
+        .with_exact(true)
+}
+
+fn invalid_input(e: SolveError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, e)
+}
+
+fn print_latex(sol: &BoundOp, target: f64, out: &mut impl Write) -> Result<(), io::Error> {
+    writeln!(out, "\\begin{{align*}}")?;
+    write!(out, "  {} &= {target}", sol.to_latex())?;
+    for step in sol.explain() {
+        // Align the steps on their equals signs
+        write!(
+            out,
+            " \\\\\n  {} &= {}",
+            step.operation_to_latex(),
+            step.result.to_latex()
+        )?;
+    }
+    writeln!(out, "\n\\end{{align*}}")
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hand() {
+        assert_eq!(parse_hand("3 3 8 8"), Ok(vec![3, 3, 8, 8]));
+        assert_eq!(parse_hand(" 1,2, 3\t4 "), Ok(vec![1, 2, 3, 4]));
+        assert_eq!(parse_hand(""), Ok(vec![]));
+        assert_eq!(parse_hand("1 2 x"), Err("invalid card `x`".to_string()));
+        assert_eq!(parse_hand("1 256"), Err("invalid card `256`".to_string()));
+    }
+
+    #[test]
+    fn test_print_hand() {
+        let args = Args::parse_from(["twentyfour", "--ops", "+-*/", "--count"]);
+        let mut out = vec![];
+        print_hand(&args, vec![1, 1, 1, 1], true, &mut out).unwrap();
+        print_hand(&args, vec![4, 4, 4, 4], false, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "1 1 1 1: 0\n1\n");
+
+        let args = Args::parse_from(["twentyfour", "--first", "--format", "json", "1", "2"]);
+        let mut out = vec![];
+        print_hand(&args, vec![1, 1, 1, 1], false, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"cards\":[1,1,1,1],\"solutions\":[]}\n"
+        );
+
+        let args = Args::parse_from(["twentyfour", "--ops", "+-*/"]);
+        let mut out = vec![];
+        print_hand(&args, vec![3, 3, 8, 8], false, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "8÷(3-8÷3)\n    8÷3 = 8/3\n    3-8/3 = 1/3\n    8÷(1/3) = 24\n"
+        );
+    }
+
+    #[test]
+    fn test_print_latex() {
+        let sol = "2^3×√9".parse::<BoundOp>().unwrap();
+        let mut out = vec![];
+        print_latex(&sol, 24.0, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\\begin{align*}\n  \
+             {2}^{3} \\times \\sqrt{9} &= 24 \\\\\n  \
+             {2}^{3} &= 8 \\\\\n  \
+             \\sqrt{9} &= 3 \\\\\n  \
+             8 \\times 3 &= 24\n\
+             \\end{align*}\n"
+        );
+    }
+
+    #[test]
+    fn test_run_batch() {
+        let path = std::env::temp_dir().join("twentyfour_test_run_batch.csv");
+        fs::write(&path, "a,b,c,d\n8,3,8,3\n1,1,1,1\n\n3,3,8,x\n").unwrap();
+        let args = Args::parse_from(["twentyfour", "--batch", path.to_str().unwrap()]);
+        let mut out = vec![];
+        let valid = run_batch(&args, &path, &mut out).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(!valid);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "8 3 8 3: solvable, 1 solution, 8÷(3-8÷3)\n1 1 1 1: unsolvable\n"
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use csv::{ReaderBuilder, WriterBuilder};
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+/// Progress of a sweep, saved so that an interrupted sweep can pick up where it stopped
+#[derive(Debug, PartialEq)]
+pub struct Checkpoint {
+    /// A description of the sweep's settings, so that a checkpoint is only resumed by the same sweep
+    pub sweep: String,
+    /// How many hands have been solved, in the order they are swept
+    pub done: usize,
+    /// How many bytes of the per-hand dataset had been written, if there is one.
+    /// Rows written after the checkpoint are dropped when the sweep resumes.
+    pub hands_len: u64,
+    /// Map of solution counts and frequency
+    pub histogram: BTreeMap<usize, u64>,
+}
+
+impl Checkpoint {
+    pub fn new(sweep: String) -> Self {
+        Self {
+            sweep,
+            done: 0,
+            hands_len: 0,
+            histogram: BTreeMap::new(),
+        }
+    }
+
+    /// Loads the checkpoint at `path` if there is one for the same sweep of `hands` hands.
+    ///
+    /// A checkpoint for a sweep with different settings is ignored, and one that has done more
+    /// hands than the sweep has is an error.
+    pub fn load(path: &Path, sweep: &str, hands: usize) -> Result<Option<Self>, io::Error> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let mut rdr = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(path)?;
+        let mut records = rdr.records();
+
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let header = records
+            .next()
+            .ok_or_else(|| invalid("empty checkpoint"))??;
+        let (Some(saved_sweep), Some(done), Some(hands_len)) =
+            (header.get(0), header.get(1), header.get(2))
+        else {
+            return Err(invalid("malformed checkpoint header"));
+        };
+        if saved_sweep != sweep {
+            return Ok(None);
+        }
+        let mut checkpoint = Self::new(sweep.to_string());
+        checkpoint.done = done.parse().map_err(|_| invalid("malformed hand count"))?;
+        if checkpoint.done > hands {
+            return Err(invalid(&format!(
+                "the checkpoint has done {} hands, but the sweep only has {hands}",
+                checkpoint.done
+            )));
+        }
+        checkpoint.hands_len = hands_len
+            .parse()
+            .map_err(|_| invalid("malformed dataset length"))?;
+
+        for record in records {
+            let record = record?;
+            let (Some(Ok(sols)), Some(Ok(freq))) =
+                (record.get(0).map(str::parse), record.get(1).map(str::parse))
+            else {
+                return Err(invalid("malformed checkpoint row"));
+            };
+            checkpoint.histogram.insert(sols, freq);
+        }
+        Ok(Some(checkpoint))
+    }
+
+    /// Saves the checkpoint to `path`, replacing it only once it is fully written.
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        let tmp = path.with_extension("tmp");
+        let mut wtr = WriterBuilder::new().flexible(true).from_path(&tmp)?;
+        wtr.write_record([
+            self.sweep.clone(),
+            self.done.to_string(),
+            self.hands_len.to_string(),
+        ])?;
+        for (k, v) in &self.histogram {
+            wtr.write_record([k.to_string(), v.to_string()])?;
+        }
+        wtr.flush()?;
+        drop(wtr);
+        fs::rename(tmp, path)
+    }
+}
+
+#[cfg(test)]
+mod checkpoint_tests {
+    use super::*;
+
+    #[test]
+    fn test_save_load() {
+        let path =
+            std::env::temp_dir().join(format!("twentyfour-dist-{}.checkpoint", std::process::id()));
+        let mut checkpoint = Checkpoint::new("cards 1..=13, ops +-×÷".to_string());
+        checkpoint.done = 42;
+        checkpoint.hands_len = 1234;
+        checkpoint.histogram.insert(0, 10);
+        checkpoint.histogram.insert(3, 7);
+        checkpoint.save(&path).unwrap();
+
+        let loaded = Checkpoint::load(&path, "cards 1..=13, ops +-×÷", 100).unwrap();
+        assert_eq!(loaded, Some(checkpoint));
+        // A different sweep starts over
+        assert_eq!(
+            Checkpoint::load(&path, "cards 1..=20, ops +-×÷", 100).unwrap(),
+            None
+        );
+        // A checkpoint past the end of the sweep cannot be resumed
+        let err = Checkpoint::load(&path, "cards 1..=13, ops +-×÷", 41).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,210 @@
+use itertools::Itertools;
+use serde_json::json;
+use std::time::{Duration, Instant};
+use twentyfour::{BoundOp, Cards, Op, Ops, Style};
+
+/// The columns of the per-hand dataset
+pub const HEADER: [&str; 9] = [
+    "cards",
+    "permutations",
+    "solvable",
+    "solutions",
+    "simplest",
+    "requires_division",
+    "requires_fractions",
+    "requires_pow",
+    "solve_time_us",
+];
+
+/// What the per-hand dataset records about one multiset of cards
+#[derive(Debug)]
+pub struct HandStats {
+    pub cards: Vec<u8>,
+    /// How many orderings of the cards there are
+    pub permutations: u64,
+    /// The number of distinct solutions
+    pub solutions: usize,
+    pub simplest: Option<BoundOp>,
+    /// Whether every solution divides
+    pub requires_division: bool,
+    /// Whether every solution goes through a value that is not an integer
+    pub requires_fractions: bool,
+    /// Whether every solution uses exponentiation
+    pub requires_pow: bool,
+    pub solve_time: Duration,
+}
+
+impl HandStats {
+    pub fn solve(cards: Vec<u8>, ops: &Ops, target: f64) -> Self {
+        let start = Instant::now();
+        let sols = Cards::with_ops(cards.clone(), ops.clone())
+            .with_target(target)
+            .with_exact(true)
+            .solve()
+            .expect("the sweep checks its hands and target up front");
+        let solve_time = start.elapsed();
+
+        let features = sols.0.iter().map(Features::of).collect::<Vec<_>>();
+        let requires = |f: fn(&Features) -> bool| !features.is_empty() && features.iter().all(f);
+        Self {
+            permutations: permutation_count(&cards)
+                .expect("the sweep checks that its hands can be counted"),
+            cards,
+            solutions: sols.len(),
+            requires_division: requires(|f| f.division),
+            requires_fractions: requires(|f| f.fractions),
+            requires_pow: requires(|f| f.pow),
+            simplest: sols
+                .0
+                .iter()
+                .zip(&features)
+                .min_by_key(|(sol, f)| {
+                    (f.pow, f.fractions, f.division, depth(sol), sol.to_string())
+                })
+                .map(|(sol, _)| sol.clone()),
+            solve_time,
+        }
+    }
+
+    /// The fields in the order of [`HEADER`], for CSV
+    pub fn record(&self) -> Vec<String> {
+        vec![
+            self.cards.iter().join(" "),
+            self.permutations.to_string(),
+            (self.solutions > 0).to_string(),
+            self.solutions.to_string(),
+            self.simplest
+                .as_ref()
+                .map(|s| s.format(Style::new()))
+                .unwrap_or_default(),
+            self.requires_division.to_string(),
+            self.requires_fractions.to_string(),
+            self.requires_pow.to_string(),
+            self.solve_time.as_micros().to_string(),
+        ]
+    }
+
+    /// The fields as a JSON object keyed by [`HEADER`]
+    pub fn json(&self) -> serde_json::Value {
+        json!({
+            "cards": self.cards,
+            "permutations": self.permutations,
+            "solvable": self.solutions > 0,
+            "solutions": self.solutions,
+            "simplest": self.simplest.as_ref().map(|s| s.format(Style::new())),
+            "requires_division": self.requires_division,
+            "requires_fractions": self.requires_fractions,
+            "requires_pow": self.requires_pow,
+            "solve_time_us": self.solve_time.as_micros() as u64,
+        })
+    }
+}
+
+/// How many orderings there are of a sorted hand, or `None` if there are too many for a `u64`
+pub fn permutation_count(hand: &[u8]) -> Option<u64> {
+    // Each run of equal cards chooses its places among the cards so far, so the count is a
+    // product of binomial coefficients, each built up so that every division is exact
+    let (mut count, mut placed) = (1u128, 0u128);
+    for (run, _) in hand.iter().dedup_with_count() {
+        for i in 1..=run as u128 {
+            placed += 1;
+            count = count.checked_mul(placed)? / i;
+        }
+    }
+    u64::try_from(count).ok()
+}
+
+/// What makes a solution harder to find
+#[derive(Debug, Default, PartialEq)]
+struct Features {
+    division: bool,
+    fractions: bool,
+    pow: bool,
+}
+
+impl Features {
+    fn of(bop: &BoundOp) -> Self {
+        let mut features = Self::default();
+        features.add(bop);
+        features
+    }
+
+    fn add(&mut self, bop: &BoundOp) {
+        match bop {
+            BoundOp::Val(_) => return,
+            BoundOp::BoundOp { op, l, r } => {
+                self.division |= *op == Op::Div;
+                self.pow |= *op == Op::Pow;
+                self.add(l);
+                self.add(r);
+            }
+            BoundOp::Unary { arg, .. } => self.add(arg),
+        }
+        // Irrational values are not integers either
+        self.fractions |= bop
+            .eval_exact()
+            .map_or(bop.eval().fract() != 0.0, |v| !v.is_integer());
+    }
+}
+
+fn depth(bop: &BoundOp) -> usize {
+    match bop {
+        BoundOp::Val(_) => 0,
+        BoundOp::BoundOp { l, r, .. } => 1 + depth(l).max(depth(r)),
+        BoundOp::Unary { arg, .. } => 1 + depth(arg),
+    }
+}
+
+#[cfg(test)]
+mod hand_tests {
+    use super::*;
+
+    #[test]
+    fn test_permutation_count() {
+        assert_eq!(permutation_count(&[1, 2, 3, 4]), Some(24));
+        assert_eq!(permutation_count(&[1, 1, 2, 3]), Some(12));
+        assert_eq!(permutation_count(&[5, 5, 5, 5]), Some(1));
+        // The multisets cover every ordered hand exactly once
+        let total = (1..=13)
+            .combinations_with_replacement(4)
+            .map(|hand| permutation_count(&hand).unwrap())
+            .sum::<u64>();
+        assert_eq!(total, 13u64.pow(4));
+        // 20! fits, but 21! does not
+        let hand = (1..=21).collect::<Vec<_>>();
+        assert_eq!(
+            permutation_count(&hand[..20]),
+            Some(2_432_902_008_176_640_000)
+        );
+        assert_eq!(permutation_count(&hand), None);
+        // Repeats keep the count small enough
+        assert_eq!(permutation_count(&[7; 200]), Some(1));
+    }
+
+    #[test]
+    fn test_stats() {
+        let ops = Ops::with_ops(vec![Op::Add, Op::Sub, Op::Mul, Op::Div]);
+        // 1, 5, 5, 5 only makes 24 as 5×(5-1÷5)
+        let stats = HandStats::solve(vec![1, 5, 5, 5], &ops, 24.0);
+        assert!(stats.solutions > 0);
+        assert!(stats.requires_division);
+        assert!(stats.requires_fractions);
+        assert!(!stats.requires_pow);
+
+        let stats = HandStats::solve(vec![1, 2, 3, 4], &ops, 24.0);
+        assert!(!stats.requires_division);
+        assert_eq!(stats.record()[4], "(1+3)×(2+4)");
+
+        // 8÷(3-8÷3) only equals 24 when values are compared exactly
+        let stats = HandStats::solve(vec![3, 3, 8, 8], &ops, 24.0);
+        assert_eq!(stats.solutions, 1);
+        assert_eq!(stats.json()["solvable"], true);
+
+        let stats = HandStats::solve(vec![1, 1, 1, 1], &ops, 24.0);
+        assert_eq!(stats.solutions, 0);
+        assert_eq!(stats.simplest, None);
+        assert!(!stats.requires_division);
+        assert_eq!(stats.record()[0], "1 1 1 1");
+        assert_eq!(stats.json()["solvable"], false);
+    }
+}
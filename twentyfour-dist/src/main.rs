@@ -1,57 +1,270 @@
+mod checkpoint;
+mod hand;
+
+use checkpoint::Checkpoint;
 use chrono::{Duration, Utc};
-use csv::Writer;
-use std::{collections::BTreeMap, io};
-use twentyfour::{Cards, Op, Ops};
+use clap::{error::ErrorKind, CommandFactory, Parser, ValueEnum};
+use csv::WriterBuilder;
+use hand::HandStats;
+use itertools::Itertools;
+use rayon::prelude::*;
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Seek, Write},
+    path::{Path, PathBuf},
+};
+use twentyfour::{Ops, TARGET};
 
-fn main() -> Result<(), io::Error> {
-    let max = 13;
+/// How many hands are solved between checkpoints
+const CHECKPOINT_EVERY: usize = 1000;
 
-    run_dist(max, Ops::default(), "dist_with_pow.csv")?;
+/// Tallies how many hands of cards have each number of solutions
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// The smallest card value
+    #[arg(long, default_value_t = 1)]
+    min: u8,
+    /// The largest card value
+    #[arg(long, default_value_t = 13)]
+    max: u8,
+    /// How many cards are in a hand
+    #[arg(short = 'n', long, default_value_t = 4)]
+    cards: usize,
+    /// The permitted operations, such as `+-*/` to leave out exponentiation
+    #[arg(long, default_value = "+-*/^")]
+    ops: Ops,
+    /// The value that solutions must equal
+    #[arg(long, default_value_t = TARGET)]
+    target: f64,
+    /// Where to write the tally. Progress is saved next to it in case the sweep is interrupted.
+    #[arg(short, long, default_value = "dist.csv")]
+    output: PathBuf,
+    /// The format of the tally
+    #[arg(long, value_enum, default_value_t = Format::Csv)]
+    format: Format,
+    /// Also write a dataset with a row for every hand: its cards, number of solutions,
+    /// the simplest solution, what every solution requires and the solve time
+    #[arg(long)]
+    hands: Option<PathBuf>,
+    /// The format of the per-hand dataset
+    #[arg(long, value_enum, default_value_t = HandsFormat::Csv)]
+    hands_format: HandsFormat,
+}
 
-    run_dist(
-        max,
-        Ops::with_ops(vec![Op::Add, Op::Sub, Op::Mul, Op::Div]),
-        "dist_without_pow.csv",
-    )?;
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Tsv,
+    Json,
+}
 
-    Ok(())
+/// Formats that rows can be appended to as the sweep goes
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum HandsFormat {
+    Csv,
+    Tsv,
+    /// JSON Lines, with one object per hand
+    Jsonl,
 }
 
-fn run_dist(max: u8, ops: Ops, outfile: &str) -> Result<(), io::Error> {
-    // Map of solution counts and frequency
-    let mut map = BTreeMap::new();
+fn main() -> Result<(), io::Error> {
+    let args = Args::parse();
+    let invalid = |msg| {
+        Args::command()
+            .error(ErrorKind::ValueValidation, msg)
+            .exit()
+    };
+    if args.min == 0 || args.min > args.max {
+        invalid("the card range must be non-empty and start at 1 or more");
+    }
+    if args.cards == 0 {
+        invalid("at least one card is required");
+    }
+    // Every count in the tally is at most the number of ordered hands
+    let values = (args.max - args.min) as u64 + 1;
+    if u32::try_from(args.cards)
+        .ok()
+        .and_then(|cards| values.checked_pow(cards))
+        .is_none()
+    {
+        invalid("there are too many hands to count");
+    }
+    if !args.target.is_finite() {
+        invalid("the target must be a finite number");
+    }
+    run_dist(&args)
+}
+
+/// Tallies how many hands with cards in the range of `args` have each number of solutions.
+///
+/// Only one hand per multiset of cards is solved, since reordering the cards does not change the
+/// solutions, and it is counted once for every ordering. Progress is saved next to the output
+/// every [`CHECKPOINT_EVERY`] hands, and a sweep with a checkpoint resumes from it.
+fn run_dist(args: &Args) -> Result<(), io::Error> {
+    let hands = (args.min..=args.max)
+        .combinations_with_replacement(args.cards)
+        .collect::<Vec<_>>();
+    let checkpoint_path = checkpoint_path(&args.output);
+    let sweep = describe(args);
+    let mut checkpoint = match Checkpoint::load(&checkpoint_path, &sweep, hands.len())? {
+        Some(checkpoint) => {
+            println!("Resuming from hand {} of {}", checkpoint.done, hands.len());
+            checkpoint
+        }
+        None => Checkpoint::new(sweep),
+    };
+    let resumed = checkpoint.done;
+    let mut hands_file = match &args.hands {
+        Some(path) => Some(open_hands(path, args.hands_format, checkpoint.hands_len)?),
+        None => None,
+    };
 
     let start_time = Utc::now().time();
-    for i in 1..=max {
-        for j in 1..=max {
-            for k in 1..=max {
-                for l in 1..=max {
-                    let cards = Cards::with_ops(vec![i, j, k, l], ops.clone());
-                    let sols = cards.solve();
-                    map.entry(sols.len()).and_modify(|e| *e += 1).or_insert(1);
-                }
-            }
+    for chunk in hands[checkpoint.done..].chunks(CHECKPOINT_EVERY) {
+        // Collecting keeps the tally independent of how the work was scheduled
+        let stats = chunk
+            .par_iter()
+            .map(|hand| HandStats::solve(hand.clone(), &args.ops, args.target))
+            .collect::<Vec<_>>();
+        for stats in &stats {
+            *checkpoint.histogram.entry(stats.solutions).or_insert(0) += stats.permutations;
         }
+        if let Some(file) = &mut hands_file {
+            checkpoint.hands_len = append_hands(file, args.hands_format, &stats)?;
+        }
+        checkpoint.done += chunk.len();
+        checkpoint.save(&checkpoint_path)?;
     }
     let elapsed = Utc::now().time() - start_time;
     print_elapsed_time(elapsed);
 
     println!(
         "Mean time per card set: {:.5}s",
-        elapsed.num_milliseconds() as f64 / 1000. / (max as f64).powi(4)
+        elapsed.num_milliseconds() as f64 / 1000. / (hands.len() - resumed).max(1) as f64
     );
 
     println!("\nSolution Frequency");
-    for (k, v) in &map {
+    for (k, v) in &checkpoint.histogram {
         println!("{}: {}", k, v);
     }
 
-    let mut wtr = Writer::from_path(outfile)?;
-    for (k, v) in map {
-        wtr.write_record(&[k.to_string(), v.to_string()])?;
+    write_histogram(&checkpoint.histogram, &args.output, args.format)?;
+    fs::remove_file(checkpoint_path)
+}
+
+fn checkpoint_path(output: &Path) -> PathBuf {
+    let mut path = output.as_os_str().to_owned();
+    path.push(".checkpoint");
+    path.into()
+}
+
+/// The settings that change the tally, which identify a checkpoint
+fn describe(args: &Args) -> String {
+    let ops = args
+        .ops
+        .binary()
+        .iter()
+        .map(|op| op.to_string())
+        .collect::<String>();
+    let mut sweep = format!(
+        "cards {}..={}, {} per hand, ops {}, target {}",
+        args.min, args.max, args.cards, ops, args.target
+    );
+    if let Some(hands) = &args.hands {
+        sweep += &format!(", hands {} as {:?}", hands.display(), args.hands_format);
+    }
+    sweep
+}
+
+/// Opens the per-hand dataset to append to, keeping the first `len` bytes.
+///
+/// A new dataset starts with a header row, unless it is JSON Lines.
+fn open_hands(path: &Path, format: HandsFormat, len: u64) -> Result<File, io::Error> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)?;
+    file.set_len(len)?;
+    file.seek(io::SeekFrom::End(0))?;
+    if len == 0 && format != HandsFormat::Jsonl {
+        let mut wtr = WriterBuilder::new()
+            .delimiter(delimiter(format))
+            .from_writer(&mut file);
+        wtr.write_record(hand::HEADER)?;
+        wtr.flush()?;
+    }
+    Ok(file)
+}
+
+/// Appends rows to the per-hand dataset, returning its new length
+fn append_hands(
+    file: &mut File,
+    format: HandsFormat,
+    stats: &[HandStats],
+) -> Result<u64, io::Error> {
+    match format {
+        HandsFormat::Csv | HandsFormat::Tsv => {
+            let mut wtr = WriterBuilder::new()
+                .delimiter(delimiter(format))
+                .has_headers(false)
+                .from_writer(&mut *file);
+            for stats in stats {
+                wtr.write_record(stats.record())?;
+            }
+            wtr.flush()?;
+        }
+        HandsFormat::Jsonl => {
+            let mut wtr = BufWriter::new(&mut *file);
+            for stats in stats {
+                writeln!(wtr, "{}", stats.json())?;
+            }
+            wtr.flush()?;
+        }
+    }
+    file.sync_data()?;
+    file.stream_position()
+}
+
+fn delimiter(format: HandsFormat) -> u8 {
+    if let HandsFormat::Tsv = format {
+        b'\t'
+    } else {
+        b','
+    }
+}
+
+fn write_histogram(
+    histogram: &BTreeMap<usize, u64>,
+    path: &Path,
+    format: Format,
+) -> Result<(), io::Error> {
+    match format {
+        Format::Csv | Format::Tsv => {
+            let delimiter = if let Format::Tsv = format {
+                b'\t'
+            } else {
+                b','
+            };
+            let mut wtr = WriterBuilder::new().delimiter(delimiter).from_path(path)?;
+            for (k, v) in histogram {
+                wtr.write_record(&[k.to_string(), v.to_string()])?;
+            }
+            wtr.flush()
+        }
+        Format::Json => {
+            let rows = histogram
+                .iter()
+                .map(|(k, v)| json!({ "solutions": k, "frequency": v }))
+                .collect::<Vec<_>>();
+            let mut file = File::create(path)?;
+            serde_json::to_writer_pretty(&mut file, &rows)?;
+            writeln!(file)
+        }
     }
-    wtr.flush()?;
-    Ok(())
 }
 
 fn print_elapsed_time(d: Duration) {
@@ -1,130 +1,444 @@
-use serde::Serialize;
-use twentyfour::{BoundOp, Cards};
+use serde::{Deserialize, Serialize};
+use twentyfour::{
+    BoundOp, Cards, Dedup, MathMl, Op, Ops, SolveError, Step, Style, UnaryOp, TARGET,
+};
 use wasm_bindgen::prelude::*;
 
+/// The TypeScript types of `solve`, which follow the serde names of the Rust types.
+///
+/// Outside of wasm builds, only the tests read it.
+#[cfg_attr(not(target_family = "wasm"), allow(dead_code))]
+const TS_TYPES: &str = r#"
+/** The name of a binary operation */
+export type Op = "add" | "sub" | "mul" | "div" | "pow";
+
+/** The name of a unary operation */
+export type UnaryOp = "fact" | "sqrt" | "neg" | "floor" | "ceil";
+
+/** How duplicate solutions are removed */
+export type Dedup = "none" | "adjacent" | "canonical";
+
+/**
+ * How `solve` searches, where every field is optional.
+ *
+ * The number of cards is the length of the array passed to `solve`.
+ */
+export interface SolveOptions {
+    /** The value that solutions must equal, 24 by default */
+    target?: number;
+    /** The permitted binary operations, all of them by default */
+    ops?: Op[];
+    /** The permitted unary operations, none by default */
+    unary?: UnaryOp[];
+    /** Compare values as exact fractions rather than floats, true by default */
+    exact?: boolean;
+    /** Only keep solutions whose every intermediate value is an integer */
+    integerOnly?: boolean;
+    /** "canonical" by default */
+    dedup?: Dedup;
+    /** Stop after this many solutions */
+    maxSolutions?: number;
+}
+
+/**
+ * An expression tree, with one key per node: a card, a binary operation or a unary
+ * operation
+ */
+export type BoundOp =
+    | { val: number }
+    | { binary: { op: Op; l: BoundOp; r: BoundOp } }
+    | { unary: { op: UnaryOp; arg: BoundOp } };
+
+/** The operation of a step, with the right operand of a binary operation */
+export type StepOp =
+    | { binary: { op: Op; rhs: Value } }
+    | { unary: UnaryOp };
+
+/**
+ * An exact fraction such as "8/3" or "-5", an approximation of an irrational value, or
+ * "undefined" for a value such as a quotient by zero
+ */
+export type Value =
+    | { exact: string }
+    | { approx: number }
+    | "undefined";
+
+/** One operation in the working of a solution */
+export interface Step {
+    /** The left operand, or the operand of a unary operation */
+    lhs: Value;
+    op: StepOp;
+    result: Value;
+    /** How many operations the step is nested in, so the last step is at depth 0 */
+    depth: number;
+}
+
+/**
+ * A presentation MathML element, with one key per element named after its tag.
+ *
+ * Tokens hold their text, and other elements hold their children.
+ */
+export type MathMl =
+    | { math: MathMl }
+    | { mrow: MathMl[] }
+    | { mn: string }
+    | { mo: string }
+    | { mfrac: [MathMl, MathMl] }
+    | { msup: [MathMl, MathMl] }
+    | { msqrt: MathMl };
+
+export interface Solution {
+    /** The solution with spaces and minimal parentheses, such as "8 ÷ (3 - 8 ÷ 3)" */
+    solution: string;
+    /** The solution as an expression tree */
+    tree: BoundOp;
+    /** The working, innermost step first */
+    steps: Step[];
+    /** The solution as a MathML `<math>` element */
+    mathml: MathMl;
+    /** Each step as a MathML `<math>` element, innermost first */
+    explanation: MathMl[];
+}
+"#;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_SECTION: &str = TS_TYPES;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "number[]")]
+    pub type JsCards;
+
+    #[wasm_bindgen(typescript_type = "SolveOptions")]
+    pub type JsSolveOptions;
+
+    #[wasm_bindgen(typescript_type = "Solution[]")]
+    pub type JsSolutions;
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct SolveOptions {
+    target: Option<f64>,
+    ops: Option<Vec<Op>>,
+    unary: Vec<UnaryOp>,
+    exact: Option<bool>,
+    integer_only: bool,
+    dedup: Dedup,
+    max_solutions: Option<usize>,
+}
+
 #[derive(Serialize)]
 struct Solution {
     solution: String,
-    explanation: Vec<String>,
+    tree: BoundOp,
+    steps: Vec<Step>,
+    /// The solution as a MathML `<math>` element
+    mathml: MathMl,
+    /// Each step as a MathML `<math>` element
+    explanation: Vec<MathMl>,
 }
 
 impl From<BoundOp> for Solution {
     fn from(bop: BoundOp) -> Self {
+        let steps = bop.explain();
         Solution {
-            solution: fmt_solution(bop.to_string()),
-            // Remove leading and trailing parantheses
-            explanation: bop
-                .explain()
-                .1
-                .into_iter()
-                .map(fmt_explanation_step)
-                .collect(),
+            solution: bop.format(Style::new().with_spaces(true)),
+            mathml: bop.to_mathml_element(),
+            explanation: steps.iter().map(Step::to_mathml_element).collect(),
+            tree: bop,
+            steps,
         }
     }
 }
 
-/// Outputs the solutions as a serialized string.
+/// Finds the solutions of any number of cards.
+///
+/// Throws if a card is not a number from 0 to 255, an option has the wrong type, there are no
+/// cards or the target is not a finite number.
 #[wasm_bindgen]
-pub fn solve24(a: u8, b: u8, c: u8, d: u8) -> String {
-    let cards = Cards::new(vec![a, b, c, d]);
-    let strs = cards
-        .solve()
-        .into_iter()
+pub fn solve(cards: JsCards, options: Option<JsSolveOptions>) -> Result<JsSolutions, JsError> {
+    let cards = serde_wasm_bindgen::from_value(cards.into())?;
+    let options = match options {
+        Some(options) => serde_wasm_bindgen::from_value(options.into())?,
+        None => SolveOptions::default(),
+    };
+    let sols = solve_cards(cards, options)?;
+    // Missing values become `null` and maps become objects, as in JSON
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    Ok(sols.serialize(&serializer)?.unchecked_into())
+}
+
+fn solve_cards(cards: Vec<u8>, options: SolveOptions) -> Result<Vec<Solution>, SolveError> {
+    let ops = options.ops.map_or_else(Ops::default, Ops::with_ops);
+    let ops = options.unary.into_iter().fold(ops, Ops::with_unary);
+    let cards = Cards::with_ops(cards, ops)
+        .with_target(options.target.unwrap_or(TARGET))
+        .with_exact(options.exact.unwrap_or(true))
+        .with_dedup(options.dedup)
+        .with_integer_only(options.integer_only);
+    Ok(cards
+        .solutions()?
+        .take(options.max_solutions.unwrap_or(usize::MAX))
         .map(Solution::from)
-        .collect::<Vec<_>>();
-    serde_json::to_string(&strs).unwrap()
+        .collect())
 }
 
-/// Removes parantheses around the BoundOp.
-///
-/// Adds spaces around all operators except exponentiation.
-/// Exponentiation is transformed to a superscript using JavaScript.
-fn fmt_explanation_step<S>(s: S) -> String
-where
-    S: Into<String>,
-{
-    let mut s = s.into();
-    s = s.replacen('(', "", 1);
-    s = s.replacen(')', "", 1);
-
-    for c in ['+', '-', '×', '÷'] {
-        // only replace first match and then return
-        if s.contains(c) {
-            s = s.replacen(c, &format!(" {c} "), 1);
-            return s;
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{de, forward_to_deserialize_any, Deserializer};
+    use twentyfour::Value;
 
-    if s.contains('^') {
-        // Replace 2^3 = 8 with 2<sup>3/sup> = 8
+    /// The last step of the explanation of `s`, written as markup
+    fn last_step(s: &str) -> String {
+        let bop = s.parse::<BoundOp>().unwrap();
+        Solution::from(bop).explanation.last().unwrap().to_string()
+    }
 
-        let (l, r) = s.split_once(" =").unwrap();
-        let (base, exp) = l.split_once('^').unwrap();
-        s = format!("{base}<sup>{exp}</sup> ={r}");
+    #[test]
+    pub fn test_explanation() {
+        assert_eq!(
+            last_step("2+3"),
+            "<math><mrow><mrow><mn>2</mn><mo>+</mo><mn>3</mn></mrow><mo>=</mo><mn>5</mn></mrow></math>"
+        );
+        assert_eq!(
+            last_step("8÷(3-8÷3)"),
+            "<math><mrow><mfrac><mn>8</mn><mfrac><mn>1</mn><mn>3</mn></mfrac></mfrac><mo>=</mo><mn>24</mn></mrow></math>"
+        );
+        assert_eq!(
+            last_step("3-8"),
+            "<math><mrow><mrow><mn>3</mn><mo>−</mo><mn>8</mn></mrow><mo>=</mo><mrow><mo>−</mo><mn>5</mn></mrow></mrow></math>"
+        );
+        assert_eq!(
+            last_step("2^3"),
+            "<math><mrow><msup><mn>2</mn><mn>3</mn></msup><mo>=</mo><mn>8</mn></mrow></math>"
+        );
     }
-    s
-}
 
-/// Some formatting for the solution, but more can be done
-fn fmt_solution<S>(s: S) -> String
-where
-    S: Into<String>,
-{
-    let s = s.into();
+    #[test]
+    pub fn test_solution_1() {
+        let bop = "((1+(2+3))×4)".parse::<BoundOp>().unwrap();
+        assert_eq!(Solution::from(bop).solution, "(1 + (2 + 3)) × 4".to_owned())
+    }
 
-    // Remove opening and trailing parantheses
-    let mut chars = s.chars();
-    chars.next();
-    chars.next_back();
+    #[test]
+    pub fn test_solution_2() {
+        let bop = "((1×2)×(3×4))".parse::<BoundOp>().unwrap();
+        assert_eq!(Solution::from(bop).solution, "1 × 2 × (3 × 4)".to_owned())
+    }
 
-    let mut s = chars.collect::<String>();
+    #[test]
+    pub fn test_solve_cards() {
+        let sols = solve_cards(vec![3, 3, 8, 8], SolveOptions::default()).unwrap();
+        assert_eq!(sols.len(), 1);
+        assert_eq!(sols[0].solution, "8 ÷ (3 - 8 ÷ 3)");
 
-    for c in ['+', '-', '×', '÷'] {
-        s = s.replace(c, &format!(" {c} "));
-    }
-    s
-}
+        let options = SolveOptions {
+            target: Some(10.0),
+            ops: Some(vec![Op::Add]),
+            ..SolveOptions::default()
+        };
+        let sols = solve_cards(vec![1, 2, 3, 4, 0], options).unwrap();
+        assert!(sols.iter().all(|sol| !sol.solution.contains(['×', '-'])));
+        assert!(!sols.is_empty());
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let options = SolveOptions {
+            max_solutions: Some(2),
+            ..SolveOptions::default()
+        };
+        assert_eq!(solve_cards(vec![1, 2, 3, 4], options).unwrap().len(), 2);
 
-    #[test]
-    pub fn test_fmt_explanation_step() {
-        assert_eq!(fmt_explanation_step("(2+3) = 5"), "2 + 3 = 5".to_owned())
+        assert!(matches!(
+            solve_cards(vec![], SolveOptions::default()),
+            Err(SolveError::NoCards)
+        ));
+        let options = SolveOptions {
+            target: Some(f64::INFINITY),
+            ..SolveOptions::default()
+        };
+        assert!(matches!(
+            solve_cards(vec![1], options),
+            Err(SolveError::InvalidTarget)
+        ));
     }
 
     #[test]
-    pub fn test_fmt_explanation_step_mul() {
-        assert_eq!(fmt_explanation_step("(1×2) = 2"), "1 × 2 = 2".to_owned())
+    pub fn test_solve_cards_rules() {
+        let options = SolveOptions {
+            integer_only: true,
+            ..SolveOptions::default()
+        };
+        assert!(solve_cards(vec![3, 3, 8, 8], options).unwrap().is_empty());
+        let options = SolveOptions {
+            ops: Some(vec![Op::Add, Op::Sub, Op::Mul, Op::Div]),
+            integer_only: true,
+            ..SolveOptions::default()
+        };
+        let sols = solve_cards(vec![3, 8, 9], options).unwrap();
+        assert_eq!(sols.len(), 1);
+        assert!(!sols[0].solution.contains("8 ÷ 3"), "{}", sols[0].solution);
+
+        let classic = SolveOptions {
+            ops: Some(vec![Op::Add, Op::Sub, Op::Mul, Op::Div]),
+            ..SolveOptions::default()
+        };
+        let sols = solve_cards(vec![2, 2, 2, 3], classic).unwrap();
+        assert!(sols.iter().all(|sol| !sol.solution.contains('^')));
+        let sols = solve_cards(vec![2, 2, 2, 3], SolveOptions::default()).unwrap();
+        assert!(sols.iter().any(|sol| sol.solution.contains('^')));
+
+        let options = SolveOptions {
+            unary: vec![UnaryOp::Fact],
+            ..SolveOptions::default()
+        };
+        let sols = solve_cards(vec![4], options).unwrap();
+        assert_eq!(sols[0].solution, "4!");
     }
 
     #[test]
-    pub fn test_fmt_explanation_step_div() {
-        assert_eq!(fmt_explanation_step("(2÷1) = 1"), "2 ÷ 1 = 1".to_owned())
+    pub fn test_solve_cards_exact() {
+        // Floats miss 8÷(3-8÷3), the only solution
+        let options = SolveOptions {
+            exact: Some(false),
+            ..SolveOptions::default()
+        };
+        assert!(solve_cards(vec![3, 3, 8, 8], options).unwrap().is_empty());
     }
 
     #[test]
-    pub fn test_fmt_explanation_step_pow() {
+    pub fn test_solution_json() {
+        let bop = "8÷(3-8÷3)".parse::<BoundOp>().unwrap();
+        let json = serde_json::to_value(Solution::from(bop.clone())).unwrap();
+        assert_eq!(json["tree"], serde_json::to_value(&bop).unwrap());
+        assert_eq!(json["steps"][1]["lhs"], serde_json::json!({ "exact": "3" }));
+        assert_eq!(
+            json["steps"][1]["op"],
+            serde_json::json!({ "binary": { "op": "sub", "rhs": { "exact": "8/3" } } })
+        );
+        assert_eq!(
+            json["steps"][2]["result"],
+            serde_json::json!({ "exact": "24" })
+        );
         assert_eq!(
-            fmt_explanation_step("(2^3) = 8"),
-            "2<sup>3</sup> = 8".to_owned()
-        )
+            json["explanation"][2],
+            serde_json::json!({ "math": { "mrow": [
+                { "mfrac": [{ "mn": "8" }, { "mfrac": [{ "mn": "1" }, { "mn": "3" }] }] },
+                { "mo": "=" },
+                { "mn": "24" }
+            ] } })
+        );
+        let steps = serde_json::to_value(Solution::from("√4".parse::<BoundOp>().unwrap())).unwrap();
+        assert_eq!(
+            steps["steps"][0]["op"],
+            serde_json::json!({ "unary": "sqrt" })
+        );
     }
 
-    #[test]
-    pub fn test_fmt_solution_1() {
-        assert_eq!(
-            fmt_solution("((1+(2+3))×4)"),
-            "(1 + (2 + 3)) × 4".to_owned()
-        )
+    /// Records the names that serde gives the variants of an enum or the fields of a struct
+    struct Names(&'static [&'static str]);
+
+    impl<'de> Deserializer<'de> for &mut Names {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: de::Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("not a struct or an enum"))
+        }
+
+        fn deserialize_struct<V: de::Visitor<'de>>(
+            self,
+            _: &'static str,
+            fields: &'static [&'static str],
+            _: V,
+        ) -> Result<V::Value, Self::Error> {
+            self.0 = fields;
+            Err(de::Error::custom("recorded"))
+        }
+
+        fn deserialize_enum<V: de::Visitor<'de>>(
+            self,
+            _: &'static str,
+            variants: &'static [&'static str],
+            _: V,
+        ) -> Result<V::Value, Self::Error> {
+            self.0 = variants;
+            Err(de::Error::custom("recorded"))
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+            option unit unit_struct newtype_struct seq tuple tuple_struct map identifier
+            ignored_any
+        }
+    }
+
+    fn names<T: de::DeserializeOwned>() -> Vec<&'static str> {
+        let mut names = Names(&[]);
+        let _ = T::deserialize(&mut names);
+        names.0.to_vec()
+    }
+
+    /// The TypeScript declaration of `name`
+    fn ts_decl(name: &str) -> &'static str {
+        let (start, end) = if let Some(start) = TS_TYPES.find(&format!("export type {name} =")) {
+            (start, ";\n")
+        } else {
+            (
+                TS_TYPES
+                    .find(&format!("export interface {name} {{"))
+                    .unwrap(),
+                "\n}",
+            )
+        };
+        let decl = &TS_TYPES[start..];
+        &decl[..decl.find(end).unwrap()]
+    }
+
+    /// The string literals of a union such as `"add" | "sub"`
+    fn ts_strings(name: &str) -> Vec<&'static str> {
+        ts_decl(name).split('"').skip(1).step_by(2).collect()
+    }
+
+    /// The key of each object in a union such as `| { exact: string } | { approx: number }`
+    fn ts_tags(name: &str) -> Vec<&'static str> {
+        ts_decl(name)
+            .split("| { ")
+            .skip(1)
+            .map(|member| &member[..member.find(':').unwrap()])
+            .collect()
+    }
+
+    /// The fields of an interface
+    fn ts_fields(name: &str) -> Vec<&'static str> {
+        ts_decl(name)
+            .lines()
+            .skip(1)
+            .map(str::trim)
+            .filter(|line| !line.starts_with("/**") && !line.starts_with('*'))
+            .map(|line| line[..line.find(':').unwrap()].trim_end_matches('?'))
+            .collect()
     }
 
     #[test]
-    pub fn test_fmt_solution_2() {
+    pub fn test_ts_types() {
+        assert_eq!(ts_strings("Op"), names::<Op>());
+        assert_eq!(ts_strings("UnaryOp"), names::<UnaryOp>());
+        assert_eq!(ts_strings("Dedup"), names::<Dedup>());
+        assert_eq!(ts_tags("BoundOp"), names::<BoundOp>());
+        assert_eq!(ts_tags("StepOp"), names::<twentyfour::StepOp>());
         assert_eq!(
-            fmt_solution("((1×2)×(3×4))"),
-            "(1 × 2) × (3 × 4)".to_owned()
-        )
+            [ts_tags("Value"), ts_strings("Value")].concat(),
+            names::<Value>()
+        );
+        assert_eq!(ts_tags("MathMl"), names::<MathMl>());
+        assert_eq!(ts_fields("Step"), names::<Step>());
+        assert_eq!(ts_fields("SolveOptions"), names::<SolveOptions>());
+
+        let bop = "√4×(3-1)".parse::<BoundOp>().unwrap();
+        let json = serde_json::to_value(Solution::from(bop)).unwrap();
+        let keys = json.as_object().unwrap().keys().map(String::as_str);
+        let mut fields = ts_fields("Solution");
+        fields.sort_unstable();
+        assert!(keys.eq(fields));
     }
 }
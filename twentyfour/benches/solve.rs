@@ -0,0 +1,44 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use itertools::Itertools;
+use twentyfour::{Cards, Dedup};
+
+fn all_hands() -> Vec<Vec<u8>> {
+    (1..=13).combinations_with_replacement(4).collect()
+}
+
+fn bench_solve(c: &mut Criterion) {
+    let hands = all_hands();
+    let mut group = c.benchmark_group("solve all 1..=13 hands");
+    group.sample_size(10);
+    group.bench_function("current", |b| {
+        b.iter(|| {
+            for hand in &hands {
+                black_box(Cards::new(hand.clone()).solve().unwrap());
+            }
+        })
+    });
+    // The baseline only removes adjacent duplicates
+    group.bench_function("current, adjacent dedup", |b| {
+        b.iter(|| {
+            for hand in &hands {
+                black_box(
+                    Cards::new(hand.clone())
+                        .with_dedup(Dedup::Adjacent)
+                        .solve()
+                        .unwrap(),
+                );
+            }
+        })
+    });
+    group.bench_function("baseline", |b| {
+        b.iter(|| {
+            for hand in &hands {
+                black_box(twentyfour_baseline::Cards::new(hand.clone()).solve());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_solve);
+criterion_main!(benches);
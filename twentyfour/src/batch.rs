@@ -0,0 +1,61 @@
+use crate::{card::CardsKey, Cards, Solutions, SolveError};
+use std::collections::HashMap;
+
+/// Solves many hands, such as every hand dealt in a tournament.
+///
+/// Each hand gets exactly what [`Cards::solve`] returns for it. A hand with the same cards, in
+/// the same order, and the same settings as an earlier one reuses its solutions instead of
+/// being solved again. Solutions come out lazily, in the order of the hands.
+///
+/// The cache is keyed on the order of the cards, since the solutions are written with the
+/// cards in the order they were given, so `8 3 8 3` and `3 3 8 8` are each solved once. It
+/// holds one entry per distinct hand until the iterator is dropped, so a batch with few
+/// repeats is better solved hand by hand.
+pub fn solve_batch(
+    hands: impl IntoIterator<Item = Cards>,
+) -> impl Iterator<Item = Result<Solutions, SolveError>> {
+    let mut cache = HashMap::<CardsKey, Result<Solutions, SolveError>>::new();
+    hands.into_iter().map(move |hand| {
+        cache
+            .entry(hand.key())
+            .or_insert_with(|| hand.solve())
+            .clone()
+    })
+}
+
+#[cfg(test)]
+mod batch_tests {
+    use super::*;
+    use crate::{Op, Ops};
+
+    #[test]
+    fn test_solve_batch() {
+        let ops = Ops::with_ops(vec![Op::Add, Op::Sub, Op::Mul, Op::Div]);
+        let hands = [
+            Cards::with_ops(vec![8, 3, 8, 3], ops.clone()).with_exact(true),
+            Cards::with_ops(vec![1, 1, 1, 1], ops.clone()),
+            Cards::with_ops(vec![3, 3, 8, 8], ops.clone()).with_exact(true),
+            Cards::with_ops(vec![3, 8, 3, 8], ops.clone()),
+            Cards::with_ops(vec![1, 2, 3, 4], ops.clone()).with_target(10.0),
+            Cards::with_ops(vec![8, 3, 8, 3], ops.clone()).with_exact(true),
+            Cards::with_ops(vec![4, 3, 2, 1], ops.clone()),
+        ];
+        let sols = solve_batch(hands.clone())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(sols.len(), hands.len());
+        // The cards keep the order they were given in
+        for (hand, sols) in hands.into_iter().zip(&sols) {
+            assert_eq!(&hand.solve().unwrap(), sols);
+        }
+        assert_eq!(sols[6].0[0].to_string(), "(4×((3+2)+1))");
+        assert_eq!(sols[0].len(), 1);
+        assert_eq!(sols[0], sols[5]);
+        assert_eq!(sols[1].len(), 0);
+        assert_eq!(sols[3].len(), 0);
+        assert!(sols[4].len() > 0);
+
+        let mut sols = solve_batch([Cards::new(vec![])]);
+        assert_eq!(sols.next(), Some(Err(SolveError::NoCards)));
+    }
+}
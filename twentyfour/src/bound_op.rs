@@ -1,15 +1,26 @@
-use super::Op;
+use super::{Canonical, Op, Rational, UnaryOp};
 use std::{fmt, vec};
 
 /// A recursive data structure representing a bound operation.
+///
+/// With the `serde` feature, it is serialized as a tree of objects with one key each:
+/// `{"val": 8}` for a card, `{"binary": {"op": "div", "l": ..., "r": ...}}` for a binary
+/// operation and `{"unary": {"op": "sqrt", "arg": ...}}` for a unary operation.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum BoundOp {
     Val(u8),
+    #[cfg_attr(feature = "serde", serde(rename = "binary"))]
     BoundOp {
         op: Op,
         l: Box<BoundOp>,
         r: Box<BoundOp>,
     },
+    Unary {
+        op: UnaryOp,
+        arg: Box<BoundOp>,
+    },
 }
 
 /// Communitative operations are considered equal.
@@ -38,14 +49,21 @@ impl PartialEq for BoundOp {
                 }
                 aop == bop && al == bl && ar == br
             }
+            (BoundOp::Unary { op: aop, arg: a }, BoundOp::Unary { op: bop, arg: b }) => {
+                aop == bop && a == b
+            }
             _ => false,
         }
     }
 }
 
 /// All solutions to a set of cards
+///
+/// With the `serde` feature, it is serialized as an array of [`BoundOp`]s.
 #[cfg_attr(wasm, wasm_bindgen)]
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Solutions(pub Vec<BoundOp>);
 
 impl Solutions {
@@ -73,37 +91,186 @@ impl BoundOp {
         match self {
             BoundOp::Val(n) => *n as f64,
             BoundOp::BoundOp { op, l, r } => (op.f())(l.eval(), r.eval()),
+            BoundOp::Unary { op, arg } => (op.f())(arg.eval()),
+        }
+    }
+
+    /// Evaluates with exact rational arithmetic.
+    ///
+    /// Returns `None` instead of `inf` or `NaN` when there is a division by zero,
+    /// and also on overflow or an irrational intermediate.
+    pub fn eval_exact(&self) -> Option<Rational> {
+        match self {
+            BoundOp::Val(n) => Some(Rational::from(*n)),
+            BoundOp::BoundOp { op, l, r } => op.eval_exact(l.eval_exact()?, r.eval_exact()?),
+            BoundOp::Unary { op, arg } => op.eval_exact(arg.eval_exact()?),
         }
     }
 
+    /// The normal form used to tell whether two solutions are genuinely distinct
+    pub fn canonical(&self) -> Canonical {
+        self.into()
+    }
+
     pub fn to_infix_notation(&self) -> String {
         match self {
             BoundOp::Val(n) => n.to_string(),
             BoundOp::BoundOp { op, l, r } => {
                 format!("({}{}{})", l.to_infix_notation(), op, r.to_infix_notation(),)
             }
+            BoundOp::Unary { op, arg } => {
+                // Nested unary operations are parenthesized so that `(3!)!` is not read as `3!!`
+                let arg = match arg.as_ref() {
+                    BoundOp::Unary {
+                        op: UnaryOp::Fact | UnaryOp::Sqrt | UnaryOp::Neg,
+                        ..
+                    } => format!("({})", arg.to_infix_notation()),
+                    _ => arg.to_infix_notation(),
+                };
+                fmt_unary(*op, arg)
+            }
         }
     }
 
-    pub fn explain(&self) -> (f64, Vec<String>) {
-        let mut explanation = vec![];
-        match self {
-            BoundOp::Val(val) => (*val as f64, explanation),
+    /// Explains how the expression is evaluated, one operation per step, innermost first.
+    ///
+    /// Each step applies an operation to the values of its operands, such as `3-8/3 = 1/3`.
+    /// Values are exact fractions unless they are irrational or too large.
+    /// A lone card takes no steps.
+    pub fn explain(&self) -> Vec<Step> {
+        let mut steps = vec![];
+        self.explain_into(0, &mut steps);
+        steps
+    }
+
+    fn explain_into(&self, depth: usize, steps: &mut Vec<Step>) -> Value {
+        let step = match self {
+            BoundOp::Val(val) => return Value::Exact(Rational::from(*val)),
             BoundOp::BoundOp { op, l, r } => {
-                let (lv, le) = l.explain();
-                explanation.extend(le);
-                let (rv, re) = r.explain();
-                explanation.extend(re);
-
-                let flat_bop = BoundOp::BoundOp {
-                    op: op.clone(),
-                    l: Box::new(BoundOp::Val(lv as u8)),
-                    r: Box::new(BoundOp::Val(rv as u8)),
-                };
-                let val = flat_bop.eval();
-                explanation.push(format!("{} = {}", flat_bop, val));
-                (val, explanation)
+                let lv = l.explain_into(depth + 1, steps);
+                let rv = r.explain_into(depth + 1, steps);
+                let result = match (lv, rv) {
+                    (Value::Exact(lv), Value::Exact(rv)) => op.eval_exact(lv, rv).map(Value::Exact),
+                    _ => None,
+                }
+                .unwrap_or_else(|| Value::from_f64((op.f())(lv.to_f64(), rv.to_f64())));
+                Step {
+                    lhs: lv,
+                    op: StepOp::Binary { op: *op, rhs: rv },
+                    result,
+                    depth,
+                }
             }
+            BoundOp::Unary { op, arg } => {
+                let av = arg.explain_into(depth + 1, steps);
+                let result = match av {
+                    Value::Exact(av) => op.eval_exact(av).map(Value::Exact),
+                    _ => None,
+                }
+                .unwrap_or_else(|| Value::from_f64((op.f())(av.to_f64())));
+                Step {
+                    lhs: av,
+                    op: StepOp::Unary(*op),
+                    result,
+                    depth,
+                }
+            }
+        };
+        let result = step.result;
+        steps.push(step);
+        result
+    }
+}
+
+/// The operation of a [`Step`]
+///
+/// With the `serde` feature, it is serialized as `{"binary": {"op": "div", "rhs": ...}}` or
+/// `{"unary": "sqrt"}`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum StepOp {
+    Binary {
+        op: Op,
+        /// The value of the right operand
+        rhs: Value,
+    },
+    Unary(UnaryOp),
+}
+
+/// One operation in the working of an expression, from [`BoundOp::explain`]
+///
+/// With the `serde` feature, it is serialized as an object with the fields below.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Step {
+    /// The value of the left operand, or of the operand of a unary operation
+    pub lhs: Value,
+    pub op: StepOp,
+    pub result: Value,
+    /// How many operations the step is nested in, so the last step is at depth 0
+    pub depth: usize,
+}
+
+/// Writes the step as a line of working, such as `3-8/3 = 1/3`, as [`Step::format`] does
+/// with the default [`Style`](crate::Style)
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.format(Default::default()))
+    }
+}
+
+/// Writes a unary operation applied to an operand that has already been written
+fn fmt_unary(op: UnaryOp, arg: String) -> String {
+    match op {
+        UnaryOp::Fact => format!("{arg}!"),
+        UnaryOp::Sqrt => format!("√{arg}"),
+        UnaryOp::Neg => format!("(-{arg})"),
+        UnaryOp::Floor => format!("⌊{arg}⌋"),
+        UnaryOp::Ceil => format!("⌈{arg}⌉"),
+    }
+}
+
+/// The value of part of an expression
+///
+/// With the `serde` feature, it is serialized as `{"exact": "8/3"}`, `{"approx": 1.414}` or
+/// `"undefined"`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Value {
+    Exact(Rational),
+    /// A finite value that is irrational or too large for [`Rational`]
+    Approx(f64),
+    /// A value that is not a finite number, such as a quotient by zero
+    Undefined,
+}
+
+impl Value {
+    /// The float as an approximate value, or [`Value::Undefined`] if it is infinite or NaN
+    fn from_f64(v: f64) -> Self {
+        if v.is_finite() {
+            Value::Approx(v)
+        } else {
+            Value::Undefined
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Value::Exact(v) => v.to_f64(),
+            Value::Approx(v) => *v,
+            Value::Undefined => f64::NAN,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Exact(v) => write!(f, "{v}"),
+            Value::Approx(v) => write!(f, "{v}"),
+            Value::Undefined => write!(f, "undefined"),
         }
     }
 }
@@ -113,3 +280,134 @@ impl fmt::Display for BoundOp {
         write!(f, "{}", self.to_infix_notation())
     }
 }
+
+#[cfg(test)]
+mod bound_op_tests {
+    use super::*;
+
+    fn explain(s: &str) -> Vec<String> {
+        s.parse::<BoundOp>()
+            .unwrap()
+            .explain()
+            .iter()
+            .map(Step::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_explain() {
+        assert_eq!(explain("((1+2)+3)×4"), ["1+2 = 3", "3+3 = 6", "6×4 = 24"]);
+        // The intermediate 1/3 used to be truncated to 0
+        assert_eq!(
+            explain("8÷(3-8÷3)"),
+            ["8÷3 = 8/3", "3-8/3 = 1/3", "8÷(1/3) = 24"]
+        );
+        assert_eq!(
+            explain("(3-8)×(2-7)-1"),
+            ["3-8 = -5", "2-7 = -5", "-5×(-5) = 25", "25-1 = 24"]
+        );
+        assert_eq!(
+            explain("(3-1)!+√4"),
+            ["3-1 = 2", "2! = 2", "√4 = 2", "2+2 = 4"]
+        );
+        // Irrational values are approximated
+        assert_eq!(
+            explain("√2×√8"),
+            [
+                "√2 = 1.4142135623730951",
+                "√8 = 2.8284271247461903",
+                "1.4142135623730951×2.8284271247461903 = 4.000000000000001"
+            ]
+        );
+        assert_eq!(
+            explain("8÷(3-3)+1"),
+            ["3-3 = 0", "8÷0 = undefined", "undefined+1 = undefined"]
+        );
+        assert_eq!(explain("24"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_explain_steps() {
+        let exact = |n, d| Value::Exact(Rational::new(n, d).unwrap());
+        let steps = "8÷(3-8÷3)".parse::<BoundOp>().unwrap().explain();
+        assert_eq!(
+            steps,
+            [
+                Step {
+                    lhs: exact(8, 1),
+                    op: StepOp::Binary {
+                        op: Op::Div,
+                        rhs: exact(3, 1),
+                    },
+                    result: exact(8, 3),
+                    depth: 2,
+                },
+                Step {
+                    lhs: exact(3, 1),
+                    op: StepOp::Binary {
+                        op: Op::Sub,
+                        rhs: exact(8, 3),
+                    },
+                    result: exact(1, 3),
+                    depth: 1,
+                },
+                Step {
+                    lhs: exact(8, 1),
+                    op: StepOp::Binary {
+                        op: Op::Div,
+                        rhs: exact(1, 3),
+                    },
+                    result: exact(24, 1),
+                    depth: 0,
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let bop = "√4×(3-1)".parse::<BoundOp>().unwrap();
+        let json = serde_json::to_string(&bop).unwrap();
+        assert_eq!(
+            json,
+            "{\"binary\":{\"op\":\"mul\",\
+             \"l\":{\"unary\":{\"op\":\"sqrt\",\"arg\":{\"val\":4}}},\
+             \"r\":{\"binary\":{\"op\":\"sub\",\"l\":{\"val\":3},\"r\":{\"val\":1}}}}}"
+        );
+        assert_eq!(
+            serde_json::from_str::<BoundOp>(&json).unwrap().to_string(),
+            bop.to_string()
+        );
+
+        let ops = crate::Ops::default().with_unary(UnaryOp::Fact);
+        let sols = crate::Cards::with_ops(vec![1, 2, 3, 4], ops)
+            .solve()
+            .unwrap();
+        let json = serde_json::to_string(&sols).unwrap();
+        assert!(json.starts_with('['));
+        let parsed = serde_json::from_str::<Solutions>(&json).unwrap();
+        assert_eq!(
+            parsed.0.iter().map(BoundOp::to_string).collect::<Vec<_>>(),
+            sols.0.iter().map(BoundOp::to_string).collect::<Vec<_>>()
+        );
+        let steps = "√4-8÷3".parse::<BoundOp>().unwrap().explain();
+        let json = serde_json::to_string(&steps[0]).unwrap();
+        assert_eq!(
+            json,
+            r#"{"lhs":{"exact":"4"},"op":{"unary":"sqrt"},"result":{"exact":"2"},"depth":1}"#
+        );
+        let json = serde_json::to_string(&steps).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<Step>>(&json).unwrap(), steps);
+        let step = "√2".parse::<BoundOp>().unwrap().explain()[0].clone();
+        assert!(serde_json::to_string(&step.result)
+            .unwrap()
+            .starts_with(r#"{"approx":1.414"#));
+        // Dividing by zero has no value, which JSON cannot write as a number
+        let steps = "8÷(3-3)".parse::<BoundOp>().unwrap().explain();
+        assert_eq!(steps[1].result, Value::Undefined);
+        let json = serde_json::to_string(&steps).unwrap();
+        assert!(json.contains(r#""result":"undefined""#), "{json}");
+        assert_eq!(serde_json::from_str::<Vec<Step>>(&json).unwrap(), steps);
+    }
+}
@@ -0,0 +1,204 @@
+use super::{BoundOp, Op, UnaryOp};
+
+/// A normal form of a [`BoundOp`].
+///
+/// Two expressions have the same canonical form when they only differ by rearrangement:
+/// associative chains of `+`/`-` and `×`/`÷` are flattened into sorted terms and factors,
+/// which also folds identities such as `a-(b-c) = (a+c)-b` and `a÷(b÷c) = (a×c)÷b`.
+/// Pairs of negated differences in a product, as in `(1-9)×(4-7) = (9-1)×(7-4)`, are folded too.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Canonical {
+    Val(u8),
+    /// The sum of `pos` minus the sum of `neg`
+    Sum {
+        pos: Vec<Canonical>,
+        neg: Vec<Canonical>,
+    },
+    /// The product of `num` divided by the product of `den`
+    Product {
+        num: Vec<Canonical>,
+        den: Vec<Canonical>,
+    },
+    Pow(Box<Canonical>, Box<Canonical>),
+    Unary(UnaryOp, Box<Canonical>),
+}
+
+impl Canonical {
+    /// Whether this is a difference that can be negated by swapping its terms
+    fn is_difference(&self) -> bool {
+        matches!(self, Canonical::Sum { neg, .. } if !neg.is_empty())
+    }
+
+    /// Negates a difference by swapping its terms.
+    fn negate(&mut self) {
+        if let Canonical::Sum { pos, neg } = self {
+            std::mem::swap(pos, neg);
+        }
+    }
+}
+
+impl From<&BoundOp> for Canonical {
+    fn from(bop: &BoundOp) -> Self {
+        match bop {
+            BoundOp::Val(n) => Canonical::Val(*n),
+            BoundOp::BoundOp {
+                op: Op::Add | Op::Sub,
+                ..
+            } => {
+                let (mut pos, mut neg) = (Vec::new(), Vec::new());
+                collect_terms(bop, false, &mut pos, &mut neg);
+                pos.sort();
+                neg.sort();
+                Canonical::Sum { pos, neg }
+            }
+            BoundOp::BoundOp {
+                op: Op::Mul | Op::Div,
+                ..
+            } => {
+                let (mut num, mut den) = (Vec::new(), Vec::new());
+                collect_factors(bop, false, &mut num, &mut den);
+
+                // Point every difference the same way, remembering how many were flipped
+                let mut flips = 0;
+                for factor in num.iter_mut().chain(den.iter_mut()) {
+                    if let Canonical::Sum { pos, neg } = factor {
+                        if !neg.is_empty() && pos < neg {
+                            factor.negate();
+                            flips += 1;
+                        }
+                    }
+                }
+                num.sort();
+                den.sort();
+                // An odd number of flips changed the sign, so flip one difference back
+                if flips % 2 == 1 {
+                    if let Some(factor) = num
+                        .iter_mut()
+                        .chain(den.iter_mut())
+                        .find(|f| f.is_difference())
+                    {
+                        factor.negate();
+                    }
+                    num.sort();
+                    den.sort();
+                }
+                Canonical::Product { num, den }
+            }
+            BoundOp::BoundOp { op: Op::Pow, l, r } => {
+                Canonical::Pow(Box::new(l.as_ref().into()), Box::new(r.as_ref().into()))
+            }
+            BoundOp::Unary { op, arg } => Canonical::Unary(*op, Box::new(arg.as_ref().into())),
+        }
+    }
+}
+
+/// Flattens a chain of `+` and `-` into positive and negative terms.
+fn collect_terms(bop: &BoundOp, negated: bool, pos: &mut Vec<Canonical>, neg: &mut Vec<Canonical>) {
+    match bop {
+        BoundOp::BoundOp { op: Op::Add, l, r } => {
+            collect_terms(l, negated, pos, neg);
+            collect_terms(r, negated, pos, neg);
+        }
+        BoundOp::BoundOp { op: Op::Sub, l, r } => {
+            collect_terms(l, negated, pos, neg);
+            collect_terms(r, !negated, pos, neg);
+        }
+        _ if negated => neg.push(bop.into()),
+        _ => pos.push(bop.into()),
+    }
+}
+
+/// Flattens a chain of `×` and `÷` into numerator and denominator factors.
+fn collect_factors(
+    bop: &BoundOp,
+    inverted: bool,
+    num: &mut Vec<Canonical>,
+    den: &mut Vec<Canonical>,
+) {
+    match bop {
+        BoundOp::BoundOp { op: Op::Mul, l, r } => {
+            collect_factors(l, inverted, num, den);
+            collect_factors(r, inverted, num, den);
+        }
+        BoundOp::BoundOp { op: Op::Div, l, r } => {
+            collect_factors(l, inverted, num, den);
+            collect_factors(r, !inverted, num, den);
+        }
+        _ if inverted => den.push(bop.into()),
+        _ => num.push(bop.into()),
+    }
+}
+
+#[cfg(test)]
+mod canonical_tests {
+    use super::*;
+
+    fn val(n: u8) -> BoundOp {
+        BoundOp::Val(n)
+    }
+
+    fn bop(op: Op, l: BoundOp, r: BoundOp) -> BoundOp {
+        BoundOp::BoundOp {
+            op,
+            l: Box::new(l),
+            r: Box::new(r),
+        }
+    }
+
+    #[test]
+    fn test_associative_commutative() {
+        // (1+2)+3 and 3+(2+1)
+        let a = bop(Op::Add, bop(Op::Add, val(1), val(2)), val(3));
+        let b = bop(Op::Add, val(3), bop(Op::Add, val(2), val(1)));
+        assert_eq!(a.canonical(), b.canonical());
+        // (1×2)×3 and 2×(3×1)
+        let a = bop(Op::Mul, bop(Op::Mul, val(1), val(2)), val(3));
+        let b = bop(Op::Mul, val(2), bop(Op::Mul, val(3), val(1)));
+        assert_eq!(a.canonical(), b.canonical());
+    }
+
+    #[test]
+    fn test_sub_div_identities() {
+        // a-(b-c) = (a+c)-b
+        let a = bop(Op::Sub, val(1), bop(Op::Sub, val(2), val(3)));
+        let b = bop(Op::Sub, bop(Op::Add, val(1), val(3)), val(2));
+        assert_eq!(a.canonical(), b.canonical());
+        // a÷(b÷c) = (a×c)÷b
+        let a = bop(Op::Div, val(1), bop(Op::Div, val(2), val(3)));
+        let b = bop(Op::Div, bop(Op::Mul, val(3), val(1)), val(2));
+        assert_eq!(a.canonical(), b.canonical());
+    }
+
+    #[test]
+    fn test_negated_factors() {
+        // (1-9)×(4-7) = (7-4)×(9-1)
+        let a = bop(
+            Op::Mul,
+            bop(Op::Sub, val(1), val(9)),
+            bop(Op::Sub, val(4), val(7)),
+        );
+        let b = bop(
+            Op::Mul,
+            bop(Op::Sub, val(7), val(4)),
+            bop(Op::Sub, val(9), val(1)),
+        );
+        assert_eq!(a.canonical(), b.canonical());
+        // but (1-9)×(7-4) is negative
+        let c = bop(
+            Op::Mul,
+            bop(Op::Sub, val(1), val(9)),
+            bop(Op::Sub, val(7), val(4)),
+        );
+        assert_ne!(a.canonical(), c.canonical());
+    }
+
+    #[test]
+    fn test_distinct() {
+        let a = bop(Op::Sub, val(1), val(2));
+        let b = bop(Op::Sub, val(2), val(1));
+        assert_ne!(a.canonical(), b.canonical());
+        let a = bop(Op::Pow, val(2), val(3));
+        let b = bop(Op::Pow, val(3), val(2));
+        assert_ne!(a.canonical(), b.canonical());
+    }
+}
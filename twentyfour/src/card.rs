@@ -1,19 +1,163 @@
-use super::{BoundOp, Ops};
-use crate::{abs_diff_eq, bound_op::Solutions};
+use super::{BoundOp, Op, Ops, Rational, UnaryOp};
+use crate::{abs_diff_eq, bound_op::Solutions, check, merge, subset::SubsetSearch, CheckError};
 use itertools::Itertools;
-use std::collections::VecDeque;
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt,
+    ops::Range,
+};
 
+/// The default value that solutions must equal
 pub const TARGET: f64 = 24.0;
 
+/// How duplicate solutions are removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Dedup {
+    /// Keep every expression the search finds
+    None,
+    /// Remove consecutive solutions that only differ by swapping the operands of `+` or `×`
+    Adjacent,
+    /// Keep one solution per [`Canonical`](crate::Canonical) form
+    #[default]
+    Canonical,
+}
+
+/// The search algorithm used by [`Cards::solve`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Strategy {
+    /// [`Strategy::Permutations`] for up to four cards, and [`Strategy::Subsets`] for more
+    #[default]
+    Auto,
+    /// Merges adjacent cards of every permutation, picking the operator of each merge by depth.
+    ///
+    /// This is quick for four cards, but the work grows factorially with the number of cards.
+    Permutations,
+    /// Builds the values that every sub-multiset of the cards can make from its splits.
+    ///
+    /// Identical cards and equal intermediate values are only combined once,
+    /// so this scales to the six numbers of a Countdown numbers round.
+    /// Values are always compared exactly.
+    Subsets,
+    /// Builds every binary expression tree over the cards, with every assignment of operators.
+    ///
+    /// Each tree is an operator applied to a tree over some of the cards and a tree over the
+    /// rest, so trying every operator with every split of the cards into two non-empty parts
+    /// reaches every tree exactly once. This is slower than the other strategies, but makes
+    /// a simple reference for them.
+    Trees,
+}
+
+/// Why a group of cards cannot be solved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// There are no cards to combine
+    NoCards,
+    /// The target is not a finite number
+    InvalidTarget,
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::NoCards => write!(f, "at least one card is required"),
+            SolveError::InvalidTarget => write!(f, "the target must be a finite number"),
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+/// The solutions nearest the target, from [`Cards::solve_closest`]
+#[derive(Debug, PartialEq)]
+pub struct Closest {
+    /// How far the solutions are from the target, which is zero when the target was reached
+    pub distance: Rational,
+    pub solutions: Solutions,
+}
+
 /// A group of cards
+///
+/// With the `serde` feature, it is serialized as an object with the fields `cards`, `ops`,
+/// `target`, `exact`, `dedup`, `strategy`, `countdown` and `integer_only`. Only `cards` is
+/// required, and the
+/// others default to the settings of [`Cards::new`]. It is deserialized through the builders.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "CardsFields"))]
 pub struct Cards {
     /// The numbers
-    ///
-    /// Array must not be empty
     cards: Vec<u8>,
     /// Permitted operations
     ops: Ops,
+    /// The value that solutions must equal
+    target: f64,
+    /// Whether solutions are checked with exact rational arithmetic
+    exact: bool,
+    /// How duplicate solutions are removed
+    dedup: Dedup,
+    /// The search algorithm
+    strategy: Strategy,
+    /// Whether the rules of the Countdown numbers round apply
+    countdown: bool,
+    /// Whether every intermediate value of a solution must be an integer
+    integer_only: bool,
+}
+
+/// The fields of [`Cards`] as they are deserialized
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct CardsFields {
+    cards: Vec<u8>,
+    #[serde(default)]
+    ops: Ops,
+    #[serde(default = "default_target")]
+    target: f64,
+    #[serde(default)]
+    exact: bool,
+    #[serde(default)]
+    dedup: Dedup,
+    #[serde(default)]
+    strategy: Strategy,
+    #[serde(default)]
+    countdown: bool,
+    #[serde(default)]
+    integer_only: bool,
+}
+
+#[cfg(feature = "serde")]
+fn default_target() -> f64 {
+    TARGET
+}
+
+#[cfg(feature = "serde")]
+impl From<CardsFields> for Cards {
+    fn from(fields: CardsFields) -> Self {
+        Cards::with_ops(fields.cards, fields.ops)
+            .with_target(fields.target)
+            .with_exact(fields.exact)
+            .with_dedup(fields.dedup)
+            .with_strategy(fields.strategy)
+            .with_countdown(fields.countdown)
+            .with_integer_only(fields.integer_only)
+    }
+}
+
+/// The fields of [`Cards`], with the target compared by its bits
+#[derive(PartialEq, Eq, Hash)]
+pub(crate) struct CardsKey {
+    cards: Vec<u8>,
+    ops: Ops,
+    target: u64,
+    exact: bool,
+    dedup: Dedup,
+    strategy: Strategy,
+    countdown: bool,
+    integer_only: bool,
 }
 
 impl Cards {
@@ -21,74 +165,273 @@ impl Cards {
         Self {
             cards,
             ops: Ops::default(),
+            target: TARGET,
+            exact: false,
+            dedup: Dedup::default(),
+            strategy: Strategy::default(),
+            countdown: false,
+            integer_only: false,
         }
     }
 
     /// Specify a custom set of operations
     pub fn with_ops(cards: Vec<u8>, ops: Ops) -> Self {
-        Self { cards, ops }
+        Self {
+            cards,
+            ops,
+            target: TARGET,
+            exact: false,
+            dedup: Dedup::default(),
+            strategy: Strategy::default(),
+            countdown: false,
+            integer_only: false,
+        }
     }
 
-    /// Finds all possible solutions
-    pub fn solve(self) -> Solutions {
-        let items = self
-            .cards_ops()
-            .into_iter()
-            .flat_map(move |(cards_perm, ops)| {
-                let q = vec![cards_perm.into_iter().map(BoundOp::Val).collect::<Vec<_>>()];
-                let mut q = VecDeque::from(q);
-
-                while let Some(bops) = q.pop_front() {
-                    let new_bops_len = bops.len() - 1;
-                    if new_bops_len == 0 {
-                        break;
-                    }
+    /// Specify a custom target instead of [`TARGET`]
+    pub fn with_target(mut self, target: f64) -> Self {
+        self.target = target;
+        self
+    }
 
-                    q.extend((0..new_bops_len).map(|i| {
-                        let mut new_bops = bops.clone();
-
-                        // Take two BoundOps from `bops` and combine them into one.
-                        new_bops.splice(
-                            i..i + 2,
-                            Some(BoundOp::BoundOp {
-                                l: Box::new(bops[i].clone()),
-                                r: Box::new(bops[i + 1].clone()),
-                                // going backwards here, but no big deal
-                                op: ops.clone().into_inner()[new_bops_len - 1].clone(),
-                            }),
-                        );
-                        new_bops
-                    }));
-                }
-                q.into_iter().filter_map(move |bops| {
-                    let bop = bops[0].clone();
-                    if abs_diff_eq(bop.eval(), TARGET) {
-                        return Some(bop);
-                    }
-                    None
-                })
-            })
-            .dedup()
-            .collect();
-        Solutions::new(items)
+    /// Accept a solution only when its exact rational value equals the target.
+    ///
+    /// By default, values are compared as floats, which can miss solutions such as `8÷(3-8÷3)`.
+    pub fn with_exact(mut self, exact: bool) -> Self {
+        self.exact = exact;
+        self
+    }
+
+    /// Specify how duplicate solutions are removed
+    pub fn with_dedup(mut self, dedup: Dedup) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    /// Specify the search algorithm
+    pub fn with_strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Play by the rules of the Countdown numbers round.
+    ///
+    /// Solutions may use any subset of the cards, and every intermediate value must be a
+    /// positive integer. These rules always use [`Strategy::Subsets`].
+    pub fn with_countdown(mut self, countdown: bool) -> Self {
+        self.countdown = countdown;
+        self
+    }
+
+    /// Only accept solutions whose every intermediate value is an integer, such as `9÷3×8`
+    /// but not `8÷3×9`.
+    ///
+    /// Solutions are filtered before duplicates are removed, so a solution is only dropped as
+    /// a duplicate of another one that also passes. [`Cards::solve_closest`] ignores this.
+    pub fn with_integer_only(mut self, integer_only: bool) -> Self {
+        self.integer_only = integer_only;
+        self
+    }
+
+    /// Finds all possible solutions, or reports why the cards cannot be solved
+    pub fn solve(self) -> Result<Solutions, SolveError> {
+        self.validate()?;
+        #[cfg(feature = "parallel")]
+        if self.strategy() == Strategy::Permutations {
+            let (dedup, integer_only) = (self.dedup, self.integer_only);
+            let items = self.solve_permutations_parallel().into_iter();
+            let items = items.filter(move |bop| !integer_only || integer_value(bop).is_some());
+            return Ok(Solutions::new(dedup_solutions(items, dedup).collect()));
+        }
+        Ok(Solutions::new(self.solutions()?.collect()))
+    }
+
+    /// Finds solutions lazily, in the same order as [`Cards::solve`].
+    ///
+    /// The search only runs as far as the iterator is advanced, so stopping early skips the
+    /// rest of the work. [`Strategy::Subsets`] tabulates every value before the first solution.
+    pub fn solutions(self) -> Result<Box<dyn Iterator<Item = BoundOp>>, SolveError> {
+        self.validate()?;
+        let (dedup, integer_only) = (self.dedup, self.integer_only);
+        let items: Box<dyn Iterator<Item = BoundOp>> = match self.strategy() {
+            Strategy::Permutations => Box::new(self.solve_permutations()),
+            Strategy::Subsets => Box::new(self.solve_subsets().into_iter()),
+            Strategy::Trees => Box::new(self.solve_trees()),
+            Strategy::Auto => unreachable!("`Cards::strategy` resolves `Strategy::Auto`"),
+        };
+        let items = items.filter(move |bop| !integer_only || integer_value(bop).is_some());
+        Ok(dedup_solutions(items, dedup))
+    }
+
+    /// Whether there is any solution, stopping at the first one found
+    pub fn is_solvable(self) -> Result<bool, SolveError> {
+        Ok(self.first_solution()?.is_some())
+    }
+
+    /// The first solution that [`Cards::solve`] would find, without searching for the others
+    pub fn first_solution(self) -> Result<Option<BoundOp>, SolveError> {
+        Ok(self.solutions()?.next())
+    }
+
+    /// Finds the solutions that reach the target, or else the ones that come closest to it.
+    ///
+    /// Values are compared exactly, as with [`Strategy::Subsets`].
+    pub fn solve_closest(self) -> Result<Closest, SolveError> {
+        self.validate()?;
+        let target = Rational::from_f64(self.target).ok_or(SolveError::InvalidTarget)?;
+        let (dedup, countdown) = (self.dedup, self.countdown);
+        let (distance, items) =
+            SubsetSearch::new(&self.cards, self.ops, countdown).closest(target, countdown);
+        Ok(Closest {
+            distance,
+            solutions: Solutions::new(dedup_solutions(items.into_iter(), dedup).collect()),
+        })
     }
 
-    /// Gets all possible operations for each permutation of the cards.
+    /// Whether `bop` is a solution, or else why not.
     ///
-    /// Note: some operations are communitative. Duplicates are removed.
-    fn cards_ops(self) -> Vec<(Vec<u8>, Ops)> {
-        let cards = self.clone().permutations_dedup();
+    /// Each card must be used exactly once, or at most once under the Countdown rules, and
+    /// only permitted operations may be used. The value must equal the target exactly. With
+    /// [`Cards::with_integer_only`], every intermediate value must also be an integer.
+    pub fn check(&self, bop: &BoundOp) -> Result<(), CheckError> {
+        check::check(
+            bop,
+            &self.cards,
+            &self.ops,
+            self.target,
+            self.countdown,
+            self.integer_only,
+        )
+    }
+
+    /// Parses an expression, such as a player's answer, and checks it like [`Cards::check`]
+    pub fn check_str(&self, s: &str) -> Result<(), CheckError> {
+        self.check(&s.parse()?)
+    }
+
+    /// Reports why the cards cannot be searched at all
+    fn validate(&self) -> Result<(), SolveError> {
+        if self.cards.is_empty() {
+            return Err(SolveError::NoCards);
+        }
+        if !self.target.is_finite() {
+            return Err(SolveError::InvalidTarget);
+        }
+        Ok(())
+    }
+
+    /// The search algorithm that is actually run, which is always [`Strategy::Subsets`] under
+    /// the Countdown rules and never [`Strategy::Auto`]
+    fn strategy(&self) -> Strategy {
+        match self.strategy {
+            _ if self.countdown => Strategy::Subsets,
+            Strategy::Auto if self.cards.len() > 4 => Strategy::Subsets,
+            Strategy::Auto => Strategy::Permutations,
+            strategy => strategy,
+        }
+    }
+
+    /// Everything that decides the solutions, for telling when two hands are solved alike
+    pub(crate) fn key(&self) -> CardsKey {
+        CardsKey {
+            cards: self.cards.clone(),
+            ops: self.ops.clone(),
+            target: self.target.to_bits(),
+            exact: self.exact,
+            dedup: self.dedup,
+            strategy: self.strategy,
+            countdown: self.countdown,
+            integer_only: self.integer_only,
+        }
+    }
 
-        let ops = self.op_product();
-        CartesianProduct::with_sizes(&[cards.len(), ops.len()])
-            .map(move |idxs| (cards[idxs[0]].clone(), ops[idxs[1]].clone()))
+    /// The target as a fraction when solutions must match it exactly.
+    ///
+    /// The outer `Option` is `None` when floats are compared instead.
+    fn exact_target(&self) -> Option<Option<Rational>> {
+        (self.exact || !self.ops.unary().is_empty()).then(|| Rational::from_f64(self.target))
+    }
+
+    /// Searches every permutation of the cards, merging adjacent cards in every order
+    fn solve_permutations(self) -> impl Iterator<Item = BoundOp> {
+        let search = self.permutation_search();
+        let mut scratch = Scratch::new(self.cards.len());
+        (0..search.len()).flat_map(move |i| search.solve(i, &mut scratch))
+    }
+
+    /// Like [`Cards::solve_permutations`], but each permutation and choice of operations
+    /// is searched on the rayon thread pool. Results are collected in the same order.
+    #[cfg(feature = "parallel")]
+    fn solve_permutations_parallel(self) -> Vec<BoundOp> {
+        use rayon::prelude::*;
+
+        let search = self.permutation_search();
+        let n = self.cards.len();
+        (0..search.len())
+            .into_par_iter()
+            .map_init(|| Scratch::new(n), |scratch, i| search.solve(i, scratch))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flatten()
             .collect()
     }
 
+    fn permutation_search(&self) -> PermutationSearch {
+        PermutationSearch {
+            perms: self.permutations_dedup(),
+            op_prods: self.op_product(),
+            unary: self.ops.unary().to_vec(),
+            max_unary: self.ops.max_unary(),
+            target: self.target,
+            exact_target: self.exact_target(),
+        }
+    }
+
+    /// Searches every binary expression tree over the cards
+    fn solve_trees(self) -> impl Iterator<Item = BoundOp> {
+        let target = self.target;
+        let exact_target = self.exact_target();
+        let unary = self.ops.unary().to_vec();
+        let max_unary = self.ops.max_unary();
+        let mut cards = self.cards;
+        cards.sort_unstable();
+        let mut forest = Forest::new(unary.is_empty() && exact_target.is_some());
+        let trees = forest.trees(&cards, self.ops.binary(), &mut HashMap::new());
+        trees.flat_map(move |i| {
+            // Without unary operations, only trees that are solutions need to be built
+            if unary.is_empty() {
+                let matches = match (exact_target, &forest.exact_vals) {
+                    (Some(exact_target), Some(exact_vals)) => {
+                        exact_target.is_some() && exact_vals[i as usize] == exact_target
+                    }
+                    _ => abs_diff_eq(forest.vals[i as usize], target),
+                };
+                return matches.then(|| forest.to_bound_op(i)).into_iter().collect();
+            }
+            with_unary(forest.to_bound_op(i), &unary, max_unary)
+                .into_iter()
+                .filter(|bop| matches_target(bop, exact_target, target))
+                .collect::<Vec<_>>()
+        })
+    }
+
+    /// Searches sub-multisets of the cards, which always compares values exactly
+    fn solve_subsets(self) -> Vec<BoundOp> {
+        let Some(target) = Rational::from_f64(self.target) else {
+            return Vec::new();
+        };
+        let search = SubsetSearch::new(&self.cards, self.ops, self.countdown);
+        if self.countdown {
+            search.subset_solutions(target)
+        } else {
+            search.solutions(target)
+        }
+    }
+
     /// Gets the Cartesian product of the operations.
     ///
     /// This is unfiltered.
-    fn op_product(self) -> Vec<Ops> {
+    fn op_product(&self) -> Vec<Ops> {
         let mut op_prods = Vec::with_capacity(self.ops.len() * (self.cards.len() - 1));
 
         let cart_prod = CartesianProduct::with_sizes(&vec![self.ops.len(); self.cards.len() - 1]);
@@ -96,7 +439,7 @@ impl Cards {
         for idxes in cart_prod {
             let ops = idxes
                 .into_iter()
-                .map(|i| self.ops.clone().into_inner()[i].clone())
+                .map(|i| self.ops.binary()[i])
                 .collect::<Vec<_>>();
             op_prods.push(Ops::with_ops(ops));
         }
@@ -104,16 +447,322 @@ impl Cards {
     }
 
     /// Returns all permutations of the cards, with duplicates removed.
-    fn permutations_dedup(self) -> Vec<Vec<u8>> {
+    fn permutations_dedup(&self) -> Vec<Vec<u8>> {
         self.cards
-            .clone()
-            .into_iter()
+            .iter()
+            .copied()
             .permutations(self.cards.len())
             .dedup()
             .collect()
     }
 }
 
+/// The work items of [`Strategy::Permutations`]: every pair of a permutation of the cards and
+/// a choice of operations, which can be searched independently
+struct PermutationSearch {
+    perms: Vec<Vec<u8>>,
+    op_prods: Vec<Ops>,
+    unary: Vec<UnaryOp>,
+    max_unary: usize,
+    target: f64,
+    exact_target: Option<Option<Rational>>,
+}
+
+/// Buffers for [`merge::merge_adjacent`], reused between work items
+struct Scratch {
+    vals: Vec<f64>,
+    exact_vals: Vec<Option<Rational>>,
+    path: Vec<usize>,
+}
+
+impl Scratch {
+    fn new(n: usize) -> Self {
+        Self {
+            vals: vec![0.0; n * (n + 1) / 2],
+            exact_vals: vec![None; n * (n + 1) / 2],
+            path: Vec::with_capacity(n),
+        }
+    }
+}
+
+impl PermutationSearch {
+    fn len(&self) -> usize {
+        self.perms.len() * self.op_prods.len()
+    }
+
+    /// The permutation and choice of operations of the `i`th work item
+    fn item(&self, i: usize) -> (&[u8], &Ops) {
+        (
+            &self.perms[i / self.op_prods.len()],
+            &self.op_prods[i % self.op_prods.len()],
+        )
+    }
+
+    /// The solutions of the `i`th work item. Work items are numbered by permutation, then by
+    /// choice of operations.
+    fn solve(&self, i: usize, scratch: &mut Scratch) -> Vec<BoundOp> {
+        let (perm, ops) = self.item(i);
+        let ops = ops.binary();
+        let (target, exact_target, n) = (self.target, self.exact_target, perm.len());
+
+        let mut sols = Vec::new();
+        if !self.unary.is_empty() {
+            // Unary operations can go anywhere, so every tree has to be built
+            merge::merge_adjacent(
+                &mut scratch.vals,
+                n,
+                ops,
+                &mut scratch.path,
+                &|_, _, _| 0.0,
+                &mut |path, _| {
+                    sols.extend(
+                        with_unary(
+                            merge::to_bound_op(perm, ops, path),
+                            &self.unary,
+                            self.max_unary,
+                        )
+                        .into_iter()
+                        .filter(|bop| matches_target(bop, exact_target, target)),
+                    )
+                },
+            );
+        } else if let Some(exact_target) = exact_target {
+            for (val, card) in scratch.exact_vals.iter_mut().zip(perm) {
+                *val = Some(Rational::from(*card));
+            }
+            merge::merge_adjacent(
+                &mut scratch.exact_vals,
+                n,
+                ops,
+                &mut scratch.path,
+                &|op, l, r| op.eval_exact(l?, r?),
+                &mut |path, val| {
+                    if exact_target.is_some() && val == exact_target {
+                        sols.push(merge::to_bound_op(perm, ops, path));
+                    }
+                },
+            );
+        } else {
+            for (val, card) in scratch.vals.iter_mut().zip(perm) {
+                *val = *card as f64;
+            }
+            merge::merge_adjacent(
+                &mut scratch.vals,
+                n,
+                ops,
+                &mut scratch.path,
+                &|op, l, r| (op.f())(l, r),
+                &mut |path, val| {
+                    if abs_diff_eq(val, target) {
+                        sols.push(merge::to_bound_op(perm, ops, path));
+                    }
+                },
+            );
+        }
+        sols
+    }
+}
+
+/// Whether `bop` equals the target, compared exactly when `exact_target` is set
+fn matches_target(bop: &BoundOp, exact_target: Option<Option<Rational>>, target: f64) -> bool {
+    match exact_target {
+        Some(exact_target) => exact_target.is_some() && bop.eval_exact() == exact_target,
+        None => abs_diff_eq(bop.eval(), target),
+    }
+}
+
+/// Expression trees stored flat, so that trees share their subtrees and every node is
+/// evaluated once, when it is added
+struct Forest {
+    nodes: Vec<Node>,
+    /// The value of each node
+    vals: Vec<f64>,
+    /// The exact value of each node, if values are compared exactly
+    exact_vals: Option<Vec<Option<Rational>>>,
+}
+
+/// A node of a [`Forest`], whose operands are earlier nodes
+#[derive(Clone, Copy)]
+enum Node {
+    Val(u8),
+    BoundOp(Op, u32, u32),
+}
+
+impl Forest {
+    fn new(exact: bool) -> Self {
+        Self {
+            nodes: Vec::new(),
+            vals: Vec::new(),
+            exact_vals: exact.then(Vec::new),
+        }
+    }
+
+    fn push(&mut self, node: Node) {
+        let (val, exact_val) = match node {
+            Node::Val(n) => (n as f64, Some(Rational::from(n))),
+            Node::BoundOp(op, l, r) => {
+                let (l, r) = (l as usize, r as usize);
+                let exact_val = self
+                    .exact_vals
+                    .as_ref()
+                    .and_then(|exact_vals| op.eval_exact(exact_vals[l]?, exact_vals[r]?));
+                ((op.f())(self.vals[l], self.vals[r]), exact_val)
+            }
+        };
+        self.nodes.push(node);
+        self.vals.push(val);
+        if let Some(exact_vals) = &mut self.exact_vals {
+            exact_vals.push(exact_val);
+        }
+    }
+
+    /// Adds every binary expression tree over the sorted multiset `cards`, with every
+    /// assignment of `ops`, and returns the positions of their roots.
+    ///
+    /// Trees are memoized by multiset, since the same parts turn up in many splits.
+    fn trees(
+        &mut self,
+        cards: &[u8],
+        ops: &[Op],
+        memo: &mut HashMap<Vec<u8>, Range<u32>>,
+    ) -> Range<u32> {
+        if let Some(trees) = memo.get(cards) {
+            return trees.clone();
+        }
+        // The parts are added first, so that the trees of `cards` are contiguous
+        let mut parts = Vec::new();
+        // Identical cards are interchangeable, so each distinct left part is only split off once
+        let mut lefts = HashSet::new();
+        for mask in 1..(1usize << cards.len()) - 1 {
+            let (l, r): (Vec<_>, Vec<_>) = (0..cards.len()).partition(|i| mask & (1 << i) != 0);
+            let l = l.into_iter().map(|i| cards[i]).collect::<Vec<_>>();
+            if !lefts.insert(l.clone()) {
+                continue;
+            }
+            let r = r.into_iter().map(|i| cards[i]).collect::<Vec<_>>();
+            parts.push((self.trees(&l, ops, memo), self.trees(&r, ops, memo)));
+        }
+
+        let start = self.nodes.len() as u32;
+        if let [card] = cards {
+            self.push(Node::Val(*card));
+        }
+        for (ls, rs) in parts {
+            for op in ops {
+                for l in ls.clone() {
+                    for r in rs.clone() {
+                        self.push(Node::BoundOp(*op, l, r));
+                    }
+                }
+            }
+        }
+        let trees = start..self.nodes.len() as u32;
+        memo.insert(cards.to_vec(), trees.clone());
+        trees
+    }
+
+    fn to_bound_op(&self, i: u32) -> BoundOp {
+        match self.nodes[i as usize] {
+            Node::Val(n) => BoundOp::Val(n),
+            Node::BoundOp(op, l, r) => BoundOp::BoundOp {
+                op,
+                l: Box::new(self.to_bound_op(l)),
+                r: Box::new(self.to_bound_op(r)),
+            },
+        }
+    }
+}
+
+/// The exact value of `bop`, or `None` if it or any intermediate value is not an integer
+fn integer_value(bop: &BoundOp) -> Option<Rational> {
+    let val = match bop {
+        BoundOp::Val(n) => Rational::from(*n),
+        BoundOp::BoundOp { op, l, r } => op.eval_exact(integer_value(l)?, integer_value(r)?)?,
+        BoundOp::Unary { op, arg } => op.eval_exact(integer_value(arg)?)?,
+    };
+    val.is_integer().then_some(val)
+}
+
+/// Removes duplicate solutions as specified by `dedup`
+fn dedup_solutions(
+    items: impl Iterator<Item = BoundOp> + 'static,
+    dedup: Dedup,
+) -> Box<dyn Iterator<Item = BoundOp>> {
+    match dedup {
+        Dedup::None => Box::new(items),
+        Dedup::Adjacent => Box::new(items.dedup()),
+        Dedup::Canonical => {
+            let mut seen = HashSet::new();
+            Box::new(items.filter(move |bop| seen.insert(bop.canonical())))
+        }
+    }
+}
+
+/// Every way of applying up to `max_unary` unary operations to the nodes of `bop`.
+///
+/// Applications that are undefined or leave the value unchanged, such as `2!`, are skipped.
+fn with_unary(bop: BoundOp, unary: &[UnaryOp], max_unary: usize) -> Vec<BoundOp> {
+    if unary.is_empty() || max_unary == 0 {
+        return vec![bop];
+    }
+    unary_variants(bop, unary, max_unary)
+        .into_iter()
+        .map(|(bop, _, _)| bop)
+        .collect()
+}
+
+/// Returns each variant along with its exact value and the number of unary operations it uses.
+fn unary_variants(
+    bop: BoundOp,
+    unary: &[UnaryOp],
+    max_unary: usize,
+) -> Vec<(BoundOp, Option<Rational>, usize)> {
+    let mut variants = match bop {
+        BoundOp::Val(n) => vec![(bop, Some(Rational::from(n)), 0)],
+        BoundOp::BoundOp { op, l, r } => {
+            let rs = unary_variants(*r, unary, max_unary);
+            let op = &op;
+            unary_variants(*l, unary, max_unary)
+                .into_iter()
+                .flat_map(|(l, lv, lu)| {
+                    rs.iter()
+                        .filter(move |(_, _, ru)| lu + ru <= max_unary)
+                        .map(move |(r, rv, ru)| {
+                            let val = lv.zip(*rv).and_then(|(lv, rv)| op.eval_exact(lv, rv));
+                            let bop = BoundOp::BoundOp {
+                                op: *op,
+                                l: Box::new(l.clone()),
+                                r: Box::new(r.clone()),
+                            };
+                            (bop, val, lu + ru)
+                        })
+                })
+                .collect()
+        }
+        BoundOp::Unary { .. } => vec![(bop.clone(), bop.eval_exact(), 0)],
+    };
+
+    // Wrap each variant, including ones wrapped here already, so unary operations can nest
+    let mut i = 0;
+    while i < variants.len() {
+        let (arg, val, used) = variants[i].clone();
+        i += 1;
+        let Some(val) = val.filter(|_| used < max_unary) else {
+            continue;
+        };
+        for op in unary {
+            if let Some(new_val) = op.eval_exact(val).filter(|v| *v != val) {
+                let bop = BoundOp::Unary {
+                    op: *op,
+                    arg: Box::new(arg.clone()),
+                };
+                variants.push((bop, Some(new_val), used + 1));
+            }
+        }
+    }
+    variants
+}
+
 pub struct CartesianProduct {
     sizes: Vec<usize>,
     indices: Vec<usize>,
@@ -156,6 +805,10 @@ impl Iterator for CartesianProduct {
 mod card_tests {
     use super::*;
     use crate::Op;
+    use std::{
+        rc::Rc,
+        time::{Duration, Instant},
+    };
 
     #[test]
     fn test_permutations_2() {
@@ -185,10 +838,21 @@ mod card_tests {
         )
     }
 
+    /// The work items of [`Strategy::Permutations`], in order
+    fn cards_ops(cards: Cards) -> Vec<(Vec<u8>, Ops)> {
+        let search = cards.permutation_search();
+        (0..search.len())
+            .map(|i| {
+                let (perm, ops) = search.item(i);
+                (perm.to_vec(), ops.clone())
+            })
+            .collect()
+    }
+
     #[test]
     fn test_cards_ops_2_dup() {
         let cards = Cards::new(vec![1, 1]);
-        let card_ops = cards.cards_ops();
+        let card_ops = cards_ops(cards);
         assert_eq!(
             card_ops,
             vec![
@@ -204,7 +868,7 @@ mod card_tests {
     #[test]
     fn test_cards_ops() {
         let cards = Cards::new(vec![1, 2]);
-        let card_ops = cards.cards_ops();
+        let card_ops = cards_ops(cards);
         assert_eq!(
             card_ops,
             vec![
@@ -225,7 +889,7 @@ mod card_tests {
     #[test]
     fn test_solve_no_sol() {
         let cards = Cards::new(vec![1, 1, 1, 1]);
-        assert_eq!(cards.solve(), Solutions::new(Vec::new()));
+        assert_eq!(cards.solve().unwrap(), Solutions::new(Vec::new()));
     }
 
     #[test]
@@ -245,7 +909,7 @@ mod card_tests {
             }),
             r: Box::new(BoundOp::Val(3)),
         };
-        assert_eq!(cards.solve(), Solutions::new(vec![unique_sol]));
+        assert_eq!(cards.solve().unwrap(), Solutions::new(vec![unique_sol]));
     }
 
     #[test]
@@ -279,22 +943,460 @@ mod card_tests {
                     }),
                 }),
             },
-            // negative version of solution 1
-            BoundOp::BoundOp {
-                op: Op::Mul,
-                l: Box::new(BoundOp::BoundOp {
-                    op: Op::Sub,
-                    l: Box::new(BoundOp::Val(7)),
-                    r: Box::new(BoundOp::Val(4)),
-                }),
+        ];
+        assert_eq!(cards.solve().unwrap(), Solutions::new(sols));
+    }
+
+    #[test]
+    fn test_solve_dedup() {
+        let cards = Cards::new(vec![1, 4, 7, 9]);
+        let adjacent = cards.clone().with_dedup(Dedup::Adjacent).solve().unwrap();
+        let raw = cards.clone().with_dedup(Dedup::None).solve().unwrap();
+        let canonical = cards.solve().unwrap();
+
+        assert_eq!(adjacent.len(), 3);
+        assert!(raw.len() > adjacent.len());
+        assert_eq!(canonical.len(), 2);
+        for sol in raw {
+            assert!(canonical.0.iter().any(|c| c.canonical() == sol.canonical()));
+        }
+    }
+
+    #[test]
+    fn test_solve_target() {
+        let cards = Cards::new(vec![2, 5]).with_target(10.0);
+        let sol = BoundOp::BoundOp {
+            op: Op::Mul,
+            l: Box::new(BoundOp::Val(2)),
+            r: Box::new(BoundOp::Val(5)),
+        };
+        assert_eq!(cards.solve().unwrap(), Solutions::new(vec![sol]));
+    }
+
+    #[test]
+    fn test_solve_exact() {
+        let sol = BoundOp::BoundOp {
+            op: Op::Div,
+            l: Box::new(BoundOp::Val(8)),
+            r: Box::new(BoundOp::BoundOp {
+                op: Op::Sub,
+                l: Box::new(BoundOp::Val(3)),
                 r: Box::new(BoundOp::BoundOp {
-                    op: Op::Sub,
-                    l: Box::new(BoundOp::Val(9)),
-                    r: Box::new(BoundOp::Val(1)),
+                    op: Op::Div,
+                    l: Box::new(BoundOp::Val(8)),
+                    r: Box::new(BoundOp::Val(3)),
                 }),
-            },
-        ];
-        assert_eq!(cards.solve(), Solutions::new(sols));
+            }),
+        };
+        assert_eq!(sol.eval_exact(), Some(Rational::from(24)));
+        // Floating point error puts this just outside of epsilon
+        assert!(!Cards::new(vec![3, 3, 8, 8])
+            .solve()
+            .unwrap()
+            .into_iter()
+            .any(|s| s == sol));
+        assert!(Cards::new(vec![3, 3, 8, 8])
+            .with_exact(true)
+            .solve()
+            .unwrap()
+            .into_iter()
+            .any(|s| s == sol));
+    }
+
+    #[test]
+    fn test_eval_exact_div_by_zero() {
+        let bop = BoundOp::BoundOp {
+            op: Op::Div,
+            l: Box::new(BoundOp::Val(2)),
+            r: Box::new(BoundOp::BoundOp {
+                op: Op::Sub,
+                l: Box::new(BoundOp::Val(1)),
+                r: Box::new(BoundOp::Val(1)),
+            }),
+        };
+        assert!(bop.eval().is_infinite());
+        assert_eq!(bop.eval_exact(), None);
+    }
+
+    #[test]
+    fn test_solve_factorial() {
+        let fact = |n| BoundOp::Unary {
+            op: UnaryOp::Fact,
+            arg: Box::new(BoundOp::Val(n)),
+        };
+        let ops = Ops::default().with_unary(UnaryOp::Fact);
+
+        // 4! with nothing else to do
+        let cards = Cards::with_ops(vec![4], ops.clone());
+        assert_eq!(cards.solve().unwrap(), Solutions::new(vec![fact(4)]));
+
+        // 1, 1, 1, 1 has no solution without factorials
+        let cards = Cards::with_ops(vec![1, 1, 1, 1], ops.clone());
+        let sols = cards.solve().unwrap();
+        assert!(sols.len() > 0);
+        for sol in sols {
+            assert_eq!(sol.eval_exact(), Some(Rational::from(24)));
+        }
+
+        // (1+1+1+1)! needs the unary operation at the root
+        let cards = Cards::with_ops(vec![1, 1, 1, 1], ops.with_max_unary(0));
+        assert_eq!(cards.solve().unwrap(), Solutions::new(Vec::new()));
+    }
+
+    #[test]
+    fn test_solve_sqrt() {
+        let ops = Ops::with_ops(vec![Op::Mul]).with_unary(UnaryOp::Sqrt);
+        let cards = Cards::with_ops(vec![2, 3, 16], ops);
+        let sols = cards.solve().unwrap();
+        assert_eq!(
+            sols.into_iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+            vec!["((2×3)×√16)"]
+        );
+    }
+
+    #[test]
+    fn test_solve_floor_ceil() {
+        // 7÷2 = 3.5 is rounded before multiplying by 6 or 8
+        let ops = Ops::with_ops(vec![Op::Mul, Op::Div])
+            .with_unary(UnaryOp::Floor)
+            .with_unary(UnaryOp::Ceil);
+        let cards = Cards::with_ops(vec![2, 6, 7], ops).with_dedup(Dedup::None);
+        let sols = cards
+            .solve()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        assert!(sols.contains(&"(⌈(7÷2)⌉×6)".to_owned()));
+        assert!(sols.iter().all(|s| s.contains('⌈') || s.contains('⌊')));
+    }
+
+    #[test]
+    fn test_solve_neg() {
+        let ops = Ops::with_ops(vec![Op::Add]).with_unary(UnaryOp::Neg);
+        let cards = Cards::with_ops(vec![30, 6], ops);
+        let sols = cards.solve().unwrap();
+        assert_eq!(
+            sols.into_iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+            vec!["(30+(-6))"]
+        );
+    }
+
+    #[test]
+    fn test_with_unary_cap() {
+        let bop = BoundOp::Val(3);
+        let variants = with_unary(bop, &[UnaryOp::Fact], 2);
+        assert_eq!(
+            variants.iter().map(|b| b.to_string()).collect::<Vec<_>>(),
+            vec!["3", "3!", "(3!)!"]
+        );
+    }
+
+    #[test]
+    fn test_solve_no_cards() {
+        assert_eq!(Cards::new(vec![]).solve(), Err(SolveError::NoCards));
+        assert!(matches!(
+            Cards::new(vec![]).solutions(),
+            Err(SolveError::NoCards)
+        ));
+        assert_eq!(
+            Cards::new(vec![]).first_solution(),
+            Err(SolveError::NoCards)
+        );
+        assert_eq!(Cards::new(vec![]).is_solvable(), Err(SolveError::NoCards));
+    }
+
+    #[test]
+    fn test_solve_invalid_target() {
+        for target in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let cards = Cards::new(vec![1, 2, 3, 4]).with_target(target);
+            assert_eq!(cards.clone().solve(), Err(SolveError::InvalidTarget));
+            assert_eq!(cards.first_solution(), Err(SolveError::InvalidTarget));
+        }
+    }
+
+    #[test]
+    fn test_strategies_agree() {
+        for cards in [
+            vec![3, 3, 8, 8],
+            vec![1, 4, 7, 9],
+            vec![2, 2, 2, 9],
+            vec![6, 6, 6, 6],
+        ] {
+            let canonical = |strategy| {
+                Cards::new(cards.clone())
+                    .with_exact(true)
+                    .with_strategy(strategy)
+                    .solve()
+                    .unwrap()
+                    .into_iter()
+                    .map(|s| s.canonical())
+                    .collect::<HashSet<_>>()
+            };
+            assert_eq!(
+                canonical(Strategy::Permutations),
+                canonical(Strategy::Subsets),
+                "{cards:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_solve_subsets_6() {
+        let mut ops = Ops::with_capacity(4);
+        ops.push(Op::Add);
+        ops.push(Op::Sub);
+        ops.push(Op::Mul);
+        ops.push(Op::Div);
+        let sols = Cards::with_ops(vec![25, 50, 75, 100, 3, 6], ops)
+            .with_target(952.0)
+            .with_strategy(Strategy::Subsets)
+            .solve()
+            .unwrap();
+        assert!(sols.len() > 0);
+        for sol in sols {
+            assert_eq!(sol.eval_exact(), Some(Rational::from_integer(952)));
+        }
+    }
+
+    #[test]
+    fn test_solve_6_quickly() {
+        // Six cards take well under a second in release builds, and much longer than that
+        // with `Strategy::Permutations`
+        let limit = if cfg!(debug_assertions) { 10 } else { 1 };
+        for ops in [Ops::default(), "+-*/".parse().unwrap()] {
+            let start = Instant::now();
+            let sols = Cards::with_ops(vec![2, 3, 5, 7, 11, 13], ops)
+                .solve()
+                .unwrap();
+            let elapsed = start.elapsed();
+            assert!(sols.len() > 0);
+            assert!(elapsed < Duration::from_secs(limit), "{elapsed:?}");
+        }
+    }
+
+    #[test]
+    fn test_solve_countdown() {
+        let ops = Ops::with_ops(vec![Op::Add, Op::Sub, Op::Mul, Op::Div]);
+        let cards = Cards::with_ops(vec![25, 50, 75, 100, 3, 6], ops).with_countdown(true);
+
+        // 6+100 uses only two of the cards
+        let sols = cards.clone().with_target(106.0).solve().unwrap();
+        assert!(sols.0.iter().any(|s| s.to_string() == "(6+100)"));
+
+        // Every intermediate value is a positive integer
+        fn positive_integers(bop: &BoundOp) -> bool {
+            let val = bop.eval_exact().unwrap();
+            val.is_integer()
+                && val > Rational::ZERO
+                && match bop {
+                    BoundOp::BoundOp { l, r, .. } => positive_integers(l) && positive_integers(r),
+                    _ => true,
+                }
+        }
+        let sols = cards.clone().with_target(952.0).solve().unwrap();
+        assert!(sols.len() > 0);
+        for sol in sols {
+            assert_eq!(sol.eval_exact(), Some(Rational::from_integer(952)));
+            assert!(positive_integers(&sol), "{sol}");
+        }
+
+        let closest = cards.with_target(952.0).solve_closest().unwrap();
+        assert_eq!(closest.distance, Rational::ZERO);
+    }
+
+    #[test]
+    fn test_solve_integer_only() {
+        let ops = "+-*/".parse().unwrap();
+        let cards = Cards::with_ops(vec![3, 8, 9], ops).with_exact(true);
+        // The one solution that is kept among its duplicates divides 8 by 3
+        let sols = cards.clone().solve().unwrap();
+        assert_eq!(sols.len(), 1);
+        assert_eq!(integer_value(&sols.0[0]), None, "{}", sols.0[0]);
+        for strategy in [Strategy::Permutations, Strategy::Subsets, Strategy::Trees] {
+            // A duplicate of it, such as 9÷3×8, is kept instead
+            let cards = cards
+                .clone()
+                .with_strategy(strategy)
+                .with_integer_only(true);
+            let sols = cards.solve().unwrap();
+            assert_eq!(sols.len(), 1);
+            assert_eq!(
+                integer_value(&sols.0[0]),
+                Some(Rational::from_integer(24)),
+                "{}",
+                sols.0[0]
+            );
+        }
+    }
+
+    #[test]
+    fn test_solve_closest() {
+        let ops = Ops::with_ops(vec![Op::Add, Op::Sub, Op::Mul, Op::Div]);
+        let closest = Cards::with_ops(vec![1, 2, 3], ops.clone())
+            .with_target(100.0)
+            .with_countdown(true)
+            .solve_closest()
+            .unwrap();
+        // (1+2)×3 = 9 is as close as these cards get
+        assert_eq!(closest.distance, Rational::from(91));
+        assert!(closest.solutions.len() > 0);
+        for sol in closest.solutions {
+            assert_eq!(sol.eval_exact(), Some(Rational::from(9)));
+        }
+
+        // 2÷3 is not a whole number, so 3-2 is closest
+        let closest = Cards::with_ops(vec![2, 3], ops)
+            .with_target(0.5)
+            .with_countdown(true)
+            .solve_closest()
+            .unwrap();
+        assert_eq!(closest.distance, Rational::new(1, 2).unwrap());
+
+        assert_eq!(
+            Cards::new(vec![1]).with_target(f64::NAN).solve_closest(),
+            Err(SolveError::InvalidTarget)
+        );
+    }
+
+    /// An expression whose value is already known, so that the reference search
+    /// only builds the trees of solutions
+    #[derive(Clone)]
+    enum Partial {
+        Val(u8),
+        BoundOp(Op, Rc<(f64, Partial)>, Rc<(f64, Partial)>),
+    }
+
+    impl From<&Partial> for BoundOp {
+        fn from(partial: &Partial) -> Self {
+            match partial {
+                Partial::Val(n) => BoundOp::Val(*n),
+                Partial::BoundOp(op, l, r) => BoundOp::BoundOp {
+                    op: *op,
+                    l: Box::new((&l.1).into()),
+                    r: Box::new((&r.1).into()),
+                },
+            }
+        }
+    }
+
+    /// Every expression that equals `target`, found by repeatedly combining any two of the
+    /// remaining expressions
+    fn reference_solutions(
+        exprs: &[Rc<(f64, Partial)>],
+        ops: &[Op],
+        target: f64,
+        sols: &mut Vec<BoundOp>,
+    ) {
+        if exprs.len() == 1 {
+            if abs_diff_eq(exprs[0].0, target) {
+                sols.push((&exprs[0].1).into());
+            }
+            return;
+        }
+        for i in 0..exprs.len() {
+            for j in 0..exprs.len() {
+                if i == j {
+                    continue;
+                }
+                let (l, r) = (exprs[i].clone(), exprs[j].clone());
+                let mut rest = exprs
+                    .iter()
+                    .enumerate()
+                    .filter(|(k, _)| *k != i && *k != j)
+                    .map(|(_, expr)| expr.clone())
+                    .collect::<Vec<_>>();
+                for op in ops {
+                    rest.push(Rc::new((
+                        (op.f())(l.0, r.0),
+                        Partial::BoundOp(*op, l.clone(), r.clone()),
+                    )));
+                    reference_solutions(&rest, ops, target, sols);
+                    rest.pop();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_trees_reference() {
+        let ops = [Op::Add, Op::Sub, Op::Mul, Op::Div, Op::Pow];
+        for hand in (1..=13).combinations_with_replacement(4) {
+            let sols = Cards::with_ops(hand.clone(), Ops::with_ops(ops.to_vec()))
+                .with_strategy(Strategy::Trees)
+                .with_dedup(Dedup::None)
+                .solve()
+                .unwrap();
+            let leaves = hand
+                .iter()
+                .map(|card| Rc::new((*card as f64, Partial::Val(*card))))
+                .collect::<Vec<_>>();
+            let mut reference = Vec::new();
+            reference_solutions(&leaves, &ops, TARGET, &mut reference);
+            // The fully parenthesized form is exactly the tree
+            let trees = |bops: Vec<BoundOp>| {
+                let mut trees = bops.iter().map(BoundOp::to_string).collect::<Vec<_>>();
+                trees.sort_unstable();
+                trees
+            };
+            // The reference makes a tree once for every order of its merges and of identical
+            // cards, while the tree search must make every tree exactly once
+            let mut reference = trees(reference);
+            reference.dedup();
+            assert_eq!(trees(sols.0), reference, "{hand:?}");
+        }
+    }
+
+    #[test]
+    fn test_all_trees_counts() {
+        // Every tree is built once: 5 shapes × 4! orders × 4³ operators
+        let ops = [Op::Add, Op::Sub, Op::Mul, Op::Div];
+        let trees = Forest::new(false).trees(&[1, 2, 3, 4], &ops, &mut HashMap::new());
+        assert_eq!(trees.len(), 5 * 24 * 64);
+        // Identical cards are interchangeable: 5 shapes × 1 order × 1 operator
+        let trees = Forest::new(false).trees(&[3, 3, 3, 3], &[Op::Add], &mut HashMap::new());
+        assert_eq!(trees.len(), 5);
+    }
+
+    #[test]
+    fn test_solutions_lazy() {
+        let cards = Cards::new(vec![3, 3, 8, 8]).with_exact(true);
+        let sols = cards.clone().solve().unwrap();
+        assert_eq!(cards.clone().solutions().unwrap().count(), sols.len());
+        assert_eq!(
+            cards.clone().first_solution().unwrap().as_ref(),
+            sols.0.first()
+        );
+        assert!(cards.is_solvable().unwrap());
+
+        assert!(!Cards::new(vec![1, 1, 1, 1]).is_solvable().unwrap());
+        for strategy in [Strategy::Permutations, Strategy::Subsets, Strategy::Trees] {
+            assert!(Cards::new(vec![1, 4, 7, 9])
+                .with_strategy(strategy)
+                .is_solvable()
+                .unwrap());
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_solve_parallel_order() {
+        for hand in [
+            vec![3, 3, 8, 8],
+            vec![1, 4, 7, 9],
+            vec![4, 6, 8, 12],
+            vec![2, 3, 4],
+        ] {
+            for dedup in [Dedup::None, Dedup::Adjacent, Dedup::Canonical] {
+                let cards = Cards::new(hand.clone()).with_dedup(dedup);
+                let sequential = cards.clone().solutions().unwrap().collect::<Vec<_>>();
+                assert_eq!(
+                    cards.solve().unwrap(),
+                    Solutions::new(sequential),
+                    "{hand:?}"
+                );
+            }
+        }
     }
 
     #[test]
@@ -487,4 +1589,44 @@ mod card_tests {
             ]
         )
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let cards = serde_json::from_str::<Cards>(r#"{"cards": [3, 3, 8, 8]}"#).unwrap();
+        assert!(cards.key() == Cards::new(vec![3, 3, 8, 8]).key());
+
+        let cards = serde_json::from_str::<Cards>(
+            r#"{
+                "cards": [25, 50, 75, 100, 3, 6],
+                "ops": {"binary": ["add", "sub", "mul", "div"], "unary": ["sqrt"]},
+                "target": 952,
+                "dedup": "adjacent",
+                "strategy": "subsets",
+                "countdown": true,
+                "integer_only": true
+            }"#,
+        )
+        .unwrap();
+        let expected = Cards::with_ops(
+            vec![25, 50, 75, 100, 3, 6],
+            Ops::with_ops(vec![Op::Add, Op::Sub, Op::Mul, Op::Div]).with_unary(UnaryOp::Sqrt),
+        )
+        .with_target(952.0)
+        .with_dedup(Dedup::Adjacent)
+        .with_strategy(Strategy::Subsets)
+        .with_countdown(true)
+        .with_integer_only(true);
+        assert!(cards.key() == expected.key());
+
+        let json = serde_json::to_string(&expected).unwrap();
+        assert!(serde_json::from_str::<Cards>(&json).unwrap().key() == expected.key());
+        assert!(serde_json::from_str::<Cards>(r#"{"target": 24}"#).is_err());
+        // The operations are checked too
+        let json = r#"{"cards": [1, 2], "ops": {"binary": ["add"], "unary": ["sqrt", "sqrt"]}}"#;
+        let cards = serde_json::from_str::<Cards>(json).unwrap();
+        assert_eq!(cards.ops.unary(), [UnaryOp::Sqrt]);
+        let json = r#"{"cards": [1, 2], "ops": {"binary": []}}"#;
+        assert!(serde_json::from_str::<Cards>(json).is_err());
+    }
 }
@@ -0,0 +1,255 @@
+use crate::{BoundOp, Op, Ops, ParseExprError, Rational, UnaryOp};
+use itertools::Itertools;
+use std::{error::Error, fmt};
+
+/// Why an expression is not a solution, from [`Cards::check`](crate::Cards::check)
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckError {
+    /// The expression could not be parsed
+    Parse(ParseExprError),
+    /// A number is not one of the cards
+    WrongCard(u8),
+    /// A card is used more times than it was dealt
+    CardReused(u8),
+    /// Some cards are not used
+    UnusedCards(Vec<u8>),
+    /// An operation is not permitted
+    DisallowedOp(Op),
+    /// A unary operation is not permitted
+    DisallowedUnaryOp(UnaryOp),
+    /// Something is divided by zero, or zero is raised to a negative power
+    DivisionByZero,
+    /// An operation is undefined for its operand, such as the square root of a negative number
+    Undefined,
+    /// An intermediate value is not a positive integer, which the Countdown rules require
+    NotPositiveInteger(f64),
+    /// An intermediate value is not an integer, which
+    /// [`Cards::with_integer_only`](crate::Cards::with_integer_only) requires
+    NotInteger(f64),
+    /// The expression does not equal the target
+    WrongValue(f64),
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckError::Parse(e) => write!(f, "{e}"),
+            CheckError::WrongCard(n) => write!(f, "{n} is not one of the cards"),
+            CheckError::CardReused(n) => write!(f, "{n} is used more times than it was dealt"),
+            CheckError::UnusedCards(cards) => {
+                write!(f, "{} must also be used", cards.iter().join(", "))
+            }
+            CheckError::DisallowedOp(op) => write!(f, "{op} is not permitted"),
+            CheckError::DisallowedUnaryOp(op) => write!(f, "{op} is not permitted"),
+            CheckError::DivisionByZero => write!(f, "division by zero"),
+            CheckError::Undefined => write!(f, "an operation is undefined for its operand"),
+            CheckError::NotPositiveInteger(v) => write!(f, "{v} is not a positive integer"),
+            CheckError::NotInteger(v) => write!(f, "{v} is not an integer"),
+            CheckError::WrongValue(v) => write!(f, "the expression equals {v}"),
+        }
+    }
+}
+
+impl Error for CheckError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CheckError::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseExprError> for CheckError {
+    fn from(e: ParseExprError) -> Self {
+        CheckError::Parse(e)
+    }
+}
+
+/// Checks `bop` against the rules that a group of cards is solved by.
+///
+/// Cards are checked first, then operations, then values.
+pub(crate) fn check(
+    bop: &BoundOp,
+    cards: &[u8],
+    ops: &Ops,
+    target: f64,
+    countdown: bool,
+    integer_only: bool,
+) -> Result<(), CheckError> {
+    let mut unused = cards.to_vec();
+    use_cards(bop, cards, &mut unused)?;
+    // Countdown solutions may leave cards out
+    if !countdown && !unused.is_empty() {
+        unused.sort_unstable();
+        return Err(CheckError::UnusedCards(unused));
+    }
+    check_ops(bop, ops)?;
+    let value = eval(bop, countdown, integer_only)?;
+    if value.is_none() || value != Rational::from_f64(target) {
+        return Err(CheckError::WrongValue(bop.eval()));
+    }
+    Ok(())
+}
+
+/// Removes the cards of `bop` from `unused`
+fn use_cards(bop: &BoundOp, cards: &[u8], unused: &mut Vec<u8>) -> Result<(), CheckError> {
+    match bop {
+        BoundOp::Val(n) => match unused.iter().position(|card| card == n) {
+            Some(i) => {
+                unused.swap_remove(i);
+                Ok(())
+            }
+            None if cards.contains(n) => Err(CheckError::CardReused(*n)),
+            None => Err(CheckError::WrongCard(*n)),
+        },
+        BoundOp::BoundOp { l, r, .. } => {
+            use_cards(l, cards, unused)?;
+            use_cards(r, cards, unused)
+        }
+        BoundOp::Unary { arg, .. } => use_cards(arg, cards, unused),
+    }
+}
+
+fn check_ops(bop: &BoundOp, ops: &Ops) -> Result<(), CheckError> {
+    match bop {
+        BoundOp::Val(_) => Ok(()),
+        BoundOp::BoundOp { op, l, r } => {
+            if !ops.binary().contains(op) {
+                return Err(CheckError::DisallowedOp(*op));
+            }
+            check_ops(l, ops)?;
+            check_ops(r, ops)
+        }
+        BoundOp::Unary { op, arg } => {
+            if !ops.unary().contains(op) {
+                return Err(CheckError::DisallowedUnaryOp(*op));
+            }
+            check_ops(arg, ops)
+        }
+    }
+}
+
+/// The exact value of `bop`, which is `None` if it is irrational or too large
+fn eval(
+    bop: &BoundOp,
+    countdown: bool,
+    integer_only: bool,
+) -> Result<Option<Rational>, CheckError> {
+    let value = match bop {
+        BoundOp::Val(n) => return Ok(Some(Rational::from(*n))),
+        BoundOp::BoundOp { op, l, r } => {
+            let (lv, rv) = (
+                eval(l, countdown, integer_only)?,
+                eval(r, countdown, integer_only)?,
+            );
+            let divides_by_zero = match op {
+                Op::Div => rv.is_some_and(|rv| rv.numer() == 0),
+                Op::Pow => {
+                    lv.is_some_and(|lv| lv.numer() == 0) && rv.is_some_and(|rv| rv.numer() < 0)
+                }
+                _ => false,
+            };
+            if divides_by_zero {
+                return Err(CheckError::DivisionByZero);
+            }
+            lv.zip(rv).and_then(|(lv, rv)| op.eval_exact(lv, rv))
+        }
+        BoundOp::Unary { op, arg } => {
+            eval(arg, countdown, integer_only)?.and_then(|av| op.eval_exact(av))
+        }
+    };
+    if value.is_none() && bop.eval().is_nan() {
+        return Err(CheckError::Undefined);
+    }
+    if countdown && !value.is_some_and(|v| v.is_integer() && v.numer() > 0) {
+        return Err(CheckError::NotPositiveInteger(bop.eval()));
+    }
+    if integer_only && !value.is_some_and(|v| v.is_integer()) {
+        return Err(CheckError::NotInteger(bop.eval()));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod check_tests {
+    use crate::{Cards, CheckError, Op, Ops, UnaryOp};
+
+    fn check(cards: &Cards, s: &str) -> Result<(), CheckError> {
+        cards.check_str(s)
+    }
+
+    #[test]
+    fn test_check() {
+        let cards = Cards::new(vec![3, 3, 8, 8]);
+        assert_eq!(check(&cards, "8÷(3-8÷3)"), Ok(()));
+        assert_eq!(check(&cards, "8 / (3 - 8 / 3)"), Ok(()));
+        assert!(matches!(
+            check(&cards, "8÷(3-8÷"),
+            Err(CheckError::Parse(_))
+        ));
+        assert_eq!(check(&cards, "8÷(3-8÷4)"), Err(CheckError::WrongCard(4)));
+        assert_eq!(check(&cards, "8÷(3-8÷8)"), Err(CheckError::CardReused(8)));
+        assert_eq!(
+            check(&cards, "8×3"),
+            Err(CheckError::UnusedCards(vec![3, 8]))
+        );
+        assert_eq!(check(&cards, "8×3+8-3"), Err(CheckError::WrongValue(29.0)));
+        assert_eq!(check(&cards, "8÷(3-3)+8"), Err(CheckError::DivisionByZero));
+        assert_eq!(
+            check(&cards, "3!+3!+8+8"),
+            Err(CheckError::DisallowedUnaryOp(UnaryOp::Fact))
+        );
+
+        let cards = Cards::with_ops(vec![1, 2, 3, 4], Ops::with_ops(vec![Op::Add, Op::Mul]));
+        assert_eq!(check(&cards, "4×3×2×1"), Ok(()));
+        assert_eq!(
+            check(&cards, "4×3×2÷1"),
+            Err(CheckError::DisallowedOp(Op::Div))
+        );
+
+        let cards = Cards::with_ops(vec![1, 2, 3, 4], Ops::default().with_unary(UnaryOp::Sqrt))
+            .with_target(10.0);
+        assert_eq!(check(&cards, "1+2+3+4"), Ok(()));
+        assert_eq!(check(&cards, "√(1-2)+3+4"), Err(CheckError::Undefined));
+
+        let cards = Cards::new(vec![1, 1, 2, 3]);
+        assert_eq!(
+            check(&cards, "(1-1)^(2-3)"),
+            Err(CheckError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn test_check_solutions() {
+        for cards in [vec![3, 3, 8, 8], vec![1, 2, 3, 4], vec![1, 5, 5, 5]] {
+            let cards = Cards::new(cards).with_exact(true);
+            for sol in cards.clone().solve().unwrap() {
+                assert_eq!(cards.check(&sol), Ok(()), "{sol}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_countdown() {
+        let cards = Cards::new(vec![25, 50, 75, 100, 3, 6])
+            .with_target(952.0)
+            .with_countdown(true);
+        assert_eq!(check(&cards, "((100+6)×3×75-50)÷25"), Ok(()));
+        assert_eq!(check(&cards, "100×6"), Err(CheckError::WrongValue(600.0)));
+        assert_eq!(
+            check(&cards, "(100-3÷6)×25"),
+            Err(CheckError::NotPositiveInteger(0.5))
+        );
+    }
+
+    #[test]
+    fn test_check_integer_only() {
+        let ops = "+-*/".parse().unwrap();
+        let cards = Cards::with_ops(vec![3, 8, 9], ops).with_integer_only(true);
+        assert_eq!(check(&cards, "9÷3×8"), Ok(()));
+        assert_eq!(
+            check(&cards, "8÷3×9"),
+            Err(CheckError::NotInteger(8.0 / 3.0))
+        );
+    }
+}
@@ -1,12 +1,26 @@
-use std::f64::EPSILON;
-
+mod batch;
 mod bound_op;
+mod canonical;
 mod card;
+mod check;
+mod merge;
 mod op;
+mod parse;
+mod pretty;
+mod rational;
+mod render;
+mod subset;
+pub use batch::*;
 pub use bound_op::*;
+pub use canonical::*;
 pub use card::*;
+pub use check::*;
 pub use op::*;
+pub use parse::*;
+pub use pretty::*;
+pub use rational::*;
+pub use render::MathMl;
 
 pub fn abs_diff_eq(a: f64, b: f64) -> bool {
-    (a - b).abs() < EPSILON
+    (a - b).abs() < f64::EPSILON
 }
@@ -0,0 +1,96 @@
+use super::{BoundOp, Op};
+use std::cmp::Ordering;
+
+/// Merges neighbouring values in every order, calling `f` with the merge positions and the value
+/// of each fully merged expression.
+///
+/// The first `len` elements of `vals` are the current values. Each merge writes the values that
+/// remain after it just past them, so one flat buffer of `len * (len + 1) / 2` elements holds
+/// every level of the search and nothing is allocated or cloned per merge. The operator of a
+/// merge is picked by how many values are left: `ops[len - 2]`.
+///
+/// `path` records the position of each merge, which is all [`to_bound_op`] needs to rebuild the
+/// expression.
+pub(crate) fn merge_adjacent<V: Copy>(
+    vals: &mut [V],
+    len: usize,
+    ops: &[Op],
+    path: &mut Vec<usize>,
+    apply: &impl Fn(Op, V, V) -> V,
+    f: &mut impl FnMut(&[usize], V),
+) {
+    if len == 1 {
+        f(path, vals[0]);
+        return;
+    }
+    let op = ops[len - 2];
+    let (cur, next) = vals.split_at_mut(len);
+    for i in 0..len - 1 {
+        // The values are few, so a plain loop beats a call to `copy_from_slice`
+        for j in 0..len - 1 {
+            next[j] = match j.cmp(&i) {
+                Ordering::Less => cur[j],
+                Ordering::Equal => apply(op, cur[i], cur[i + 1]),
+                Ordering::Greater => cur[j + 1],
+            };
+        }
+
+        path.push(i);
+        merge_adjacent(next, len - 1, ops, path, apply, f);
+        path.pop();
+    }
+}
+
+/// Builds the tree made by merging neighbouring `cards` at the positions in `path`.
+pub(crate) fn to_bound_op(cards: &[u8], ops: &[Op], path: &[usize]) -> BoundOp {
+    let mut bops = cards
+        .iter()
+        .map(|card| BoundOp::Val(*card))
+        .collect::<Vec<_>>();
+    for &i in path {
+        let op = ops[bops.len() - 2];
+        let r = bops.remove(i + 1);
+        let l = std::mem::replace(&mut bops[i], BoundOp::Val(0));
+        bops[i] = BoundOp::BoundOp {
+            op,
+            l: Box::new(l),
+            r: Box::new(r),
+        };
+    }
+    bops.pop().unwrap()
+}
+
+#[cfg(test)]
+mod merge_tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_adjacent() {
+        let (cards, ops) = ([1, 2, 3], [Op::Add, Op::Mul]);
+        let mut vals = vec![0.0; 6];
+        vals[..3].copy_from_slice(&[1.0, 2.0, 3.0]);
+        let mut merged = Vec::new();
+        merge_adjacent(
+            &mut vals,
+            3,
+            &ops,
+            &mut Vec::new(),
+            &|op, l, r| (op.f())(l, r),
+            &mut |path, val| merged.push((to_bound_op(&cards, &ops, path).to_string(), val)),
+        );
+        assert_eq!(
+            merged,
+            vec![
+                ("((1×2)+3)".to_string(), 5.0),
+                ("(1+(2×3))".to_string(), 7.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_bound_op() {
+        // 8 ÷ (3 - 8 ÷ 3)
+        let bop = to_bound_op(&[8, 3, 8, 3], &[Op::Div, Op::Sub, Op::Div], &[2, 1, 0]);
+        assert_eq!(bop.to_string(), "(8÷(3-(8÷3)))");
+    }
+}
@@ -1,7 +1,12 @@
-use std::{fmt, vec};
+use crate::Rational;
+use std::{error::Error, fmt, str::FromStr, vec};
 
 /// The type of arithmetic operation
-#[derive(Debug, Clone, PartialEq)]
+///
+/// With the `serde` feature, it is serialized as its lowercase name, such as `"div"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Op {
     Add,
     Sub,
@@ -20,6 +25,33 @@ impl Op {
             Op::Pow => |a, b| a.powf(b),
         }
     }
+
+    /// Applies the operation with exact arithmetic.
+    ///
+    /// Returns `None` for division by zero, overflow, or an irrational result.
+    pub fn eval_exact(&self, a: Rational, b: Rational) -> Option<Rational> {
+        match self {
+            Op::Add => a.checked_add(b),
+            Op::Sub => a.checked_sub(b),
+            Op::Mul => a.checked_mul(b),
+            Op::Div => a.checked_div(b),
+            Op::Pow => a.checked_pow(b),
+        }
+    }
+
+    /// The operation written as `symbol`.
+    ///
+    /// Besides the symbols that [`Op`] is displayed with, ASCII `*` and `/` are accepted.
+    pub fn from_symbol(symbol: char) -> Option<Self> {
+        match symbol {
+            '+' => Some(Op::Add),
+            '-' => Some(Op::Sub),
+            '*' | '×' => Some(Op::Mul),
+            '/' | '÷' => Some(Op::Div),
+            '^' => Some(Op::Pow),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Op {
@@ -35,30 +67,190 @@ impl fmt::Display for Op {
     }
 }
 
-/// A newtype collection of operations
-#[derive(Debug, Clone, PartialEq)]
-pub struct Ops(Vec<Op>);
+/// The largest number that the factorial is applied to
+pub const MAX_FACTORIAL: u8 = 20;
+
+/// The type of unary operation
+///
+/// With the `serde` feature, it is serialized as its lowercase name, such as `"sqrt"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum UnaryOp {
+    Fact,
+    Sqrt,
+    Neg,
+    Floor,
+    Ceil,
+}
+
+impl UnaryOp {
+    pub fn f(&self) -> fn(f64) -> f64 {
+        match self {
+            UnaryOp::Fact => |a| {
+                if a.fract() != 0.0 || !(0.0..=MAX_FACTORIAL as f64).contains(&a) {
+                    return f64::NAN;
+                }
+                (1..=a as u64).product::<u64>() as f64
+            },
+            UnaryOp::Sqrt => |a| a.sqrt(),
+            UnaryOp::Neg => |a| -a,
+            UnaryOp::Floor => |a| a.floor(),
+            UnaryOp::Ceil => |a| a.ceil(),
+        }
+    }
+
+    /// Applies the operation with exact arithmetic.
+    ///
+    /// Returns `None` when the operand is out of the operation's domain.
+    pub fn eval_exact(&self, a: Rational) -> Option<Rational> {
+        match self {
+            UnaryOp::Fact => {
+                if !a.is_integer() || !(0..=MAX_FACTORIAL as i128).contains(&a.numer()) {
+                    return None;
+                }
+                Some(Rational::from_integer((1..=a.numer()).product()))
+            }
+            UnaryOp::Sqrt => a.checked_pow(Rational::new(1, 2)?),
+            UnaryOp::Neg => a.checked_neg(),
+            UnaryOp::Floor => Some(Rational::from_integer(a.numer().div_euclid(a.denom()))),
+            UnaryOp::Ceil => Some(Rational::from_integer(
+                -(a.numer().checked_neg()?.div_euclid(a.denom())),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            UnaryOp::Fact => "!",
+            UnaryOp::Sqrt => "√",
+            UnaryOp::Neg => "-",
+            UnaryOp::Floor => "⌊⌋",
+            UnaryOp::Ceil => "⌈⌉",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// The default number of unary operations that may appear in one solution
+pub const DEFAULT_MAX_UNARY: usize = 1;
+
+/// A collection of operations
+///
+/// Binary operations are always available to the solver.
+/// Unary operations are opt-in with [`Ops::with_unary`].
+///
+/// With the `serde` feature, it is serialized as an object such as
+/// `{"binary": ["add", "mul"], "unary": ["sqrt"], "max_unary": 1}`,
+/// where `unary` and `max_unary` may be left out.
+///
+/// Like [`Ops::from_str`], it is deserialized through the builders, so repeated operations
+/// are dropped and an empty `binary` is an error.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "OpsFields"))]
+pub struct Ops {
+    #[cfg_attr(feature = "serde", serde(rename = "binary"))]
+    ops: Vec<Op>,
+    unary: Vec<UnaryOp>,
+    /// How many unary operations may be applied in total, including nested ones
+    max_unary: usize,
+}
+
+/// The fields of [`Ops`] as they are deserialized, before they are checked
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct OpsFields {
+    binary: Vec<Op>,
+    #[serde(default)]
+    unary: Vec<UnaryOp>,
+    #[serde(default = "default_max_unary")]
+    max_unary: usize,
+}
+
+#[cfg(feature = "serde")]
+fn default_max_unary() -> usize {
+    DEFAULT_MAX_UNARY
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<OpsFields> for Ops {
+    type Error = ParseOpsError;
+
+    fn try_from(fields: OpsFields) -> Result<Self, Self::Error> {
+        let mut binary = Vec::new();
+        for op in fields.binary {
+            if !binary.contains(&op) {
+                binary.push(op);
+            }
+        }
+        if binary.is_empty() {
+            return Err(ParseOpsError::Empty);
+        }
+        let ops = fields
+            .unary
+            .into_iter()
+            .fold(Self::with_ops(binary), Self::with_unary);
+        Ok(ops.with_max_unary(fields.max_unary))
+    }
+}
 
 impl Ops {
     pub fn with_capacity(size: usize) -> Self {
-        Self(Vec::with_capacity(size))
+        Self::with_ops(Vec::with_capacity(size))
     }
 
     pub fn with_ops(ops: Vec<Op>) -> Self {
-        Self(ops.to_vec())
+        Self {
+            ops,
+            unary: Vec::new(),
+            max_unary: DEFAULT_MAX_UNARY,
+        }
+    }
+
+    /// Permit a unary operation.
+    ///
+    /// Solutions that use unary operations are only accepted if they equal the target exactly.
+    pub fn with_unary(mut self, op: UnaryOp) -> Self {
+        if !self.unary.contains(&op) {
+            self.unary.push(op);
+        }
+        self
+    }
+
+    /// Limit how many unary operations may appear in one solution.
+    ///
+    /// This keeps the search finite, since unary operations can be nested indefinitely.
+    pub fn with_max_unary(mut self, max_unary: usize) -> Self {
+        self.max_unary = max_unary;
+        self
     }
 
     pub fn into_inner(self) -> Vec<Op> {
-        self.0
+        self.ops
+    }
+
+    pub fn binary(&self) -> &[Op] {
+        &self.ops
+    }
+
+    pub fn unary(&self) -> &[UnaryOp] {
+        &self.unary
+    }
+
+    pub fn max_unary(&self) -> usize {
+        self.max_unary
     }
 
     pub fn push(&mut self, op: Op) {
-        self.0.push(op)
+        self.ops.push(op)
     }
 
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.ops.len()
     }
 }
 
@@ -66,12 +258,90 @@ impl Iterator for Ops {
     type Item = Op;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.pop()
+        self.ops.pop()
+    }
+}
+
+/// Why a string of operation symbols could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseOpsError {
+    /// There are no operations
+    Empty,
+    /// A character is not the symbol of an operation
+    Unknown(char),
+}
+
+impl fmt::Display for ParseOpsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseOpsError::Empty => write!(f, "at least one operation is required"),
+            ParseOpsError::Unknown(c) => write!(f, "unknown operation `{c}`"),
+        }
+    }
+}
+
+impl Error for ParseOpsError {}
+
+/// Parses binary operations from their symbols, such as `+-*/^` or `+-×÷`.
+///
+/// Repeated symbols and whitespace are ignored.
+impl FromStr for Ops {
+    type Err = ParseOpsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ops = Vec::new();
+        for c in s.chars().filter(|c| !c.is_whitespace()) {
+            let op = Op::from_symbol(c).ok_or(ParseOpsError::Unknown(c))?;
+            if !ops.contains(&op) {
+                ops.push(op);
+            }
+        }
+        if ops.is_empty() {
+            return Err(ParseOpsError::Empty);
+        }
+        Ok(Self::with_ops(ops))
     }
 }
 
 impl Default for Ops {
     fn default() -> Self {
-        Self(vec![Op::Add, Op::Sub, Op::Mul, Op::Div, Op::Pow])
+        Self::with_ops(vec![Op::Add, Op::Sub, Op::Mul, Op::Div, Op::Pow])
+    }
+}
+
+#[cfg(test)]
+mod op_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ops() {
+        assert_eq!("+-*/^".parse(), Ok(Ops::default()));
+        assert_eq!("+-×÷^".parse(), Ok(Ops::default()));
+        assert_eq!("* + *".parse(), Ok(Ops::with_ops(vec![Op::Mul, Op::Add])));
+        assert_eq!("+%".parse::<Ops>(), Err(ParseOpsError::Unknown('%')));
+        assert_eq!("".parse::<Ops>(), Err(ParseOpsError::Empty));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let ops = serde_json::from_str::<Ops>(
+            r#"{"binary": ["add", "mul", "add"], "unary": ["sqrt", "sqrt"], "max_unary": 1}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            ops,
+            Ops::with_ops(vec![Op::Add, Op::Mul])
+                .with_unary(UnaryOp::Sqrt)
+                .with_max_unary(1)
+        );
+        assert_eq!(
+            serde_json::to_string(&ops).unwrap(),
+            r#"{"binary":["add","mul"],"unary":["sqrt"],"max_unary":1}"#
+        );
+        let err = serde_json::from_str::<Ops>(r#"{"binary": []}"#).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("at least one operation is required"));
     }
 }
@@ -0,0 +1,392 @@
+use crate::{BoundOp, Op, UnaryOp};
+use std::{error::Error, fmt, ops::Range, str::FromStr};
+
+/// Why an expression could not be parsed, and where
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseExprError {
+    pub kind: ExprErrorKind,
+    /// The byte range of the input that the error is about
+    pub span: Range<usize>,
+}
+
+/// The kinds of [`ParseExprError`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprErrorKind {
+    /// There is nothing but whitespace
+    Empty,
+    /// A character is not part of any number or operation
+    UnknownSymbol(char),
+    /// A word is not the name of an operation
+    UnknownName(String),
+    /// A number is too large to be a card
+    NumberTooLarge,
+    /// A number or an opening bracket was expected, such as after an operator
+    ExpectedOperand,
+    /// An operator was expected, such as between two numbers
+    ExpectedOperator,
+    /// An opening bracket is never closed
+    Unclosed(char),
+    /// A closing bracket has no opening bracket
+    Unopened(char),
+}
+
+impl fmt::Display for ExprErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExprErrorKind::Empty => write!(f, "the expression is empty"),
+            ExprErrorKind::UnknownSymbol(c) => write!(f, "unknown symbol `{c}`"),
+            ExprErrorKind::UnknownName(name) => write!(f, "unknown operation `{name}`"),
+            ExprErrorKind::NumberTooLarge => write!(f, "numbers cannot be larger than {}", u8::MAX),
+            ExprErrorKind::ExpectedOperand => write!(f, "expected a number or `(`"),
+            ExprErrorKind::ExpectedOperator => write!(f, "expected an operator"),
+            ExprErrorKind::Unclosed(c) => write!(f, "`{c}` is never closed"),
+            ExprErrorKind::Unopened(c) => write!(f, "`{c}` has no opening bracket"),
+        }
+    }
+}
+
+impl fmt::Display for ParseExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}..{}", self.kind, self.span.start, self.span.end)
+    }
+}
+
+impl Error for ParseExprError {}
+
+impl ParseExprError {
+    fn new(kind: ExprErrorKind, span: Range<usize>) -> Self {
+        Self { kind, span }
+    }
+}
+
+/// Parses an expression such as `8÷(3-8÷3)` or `8/(3-8/3)`.
+///
+/// Operations are written as they are displayed, or with ASCII `*`, `/` and `**`.
+/// Exponentiation binds tightest and is right-associative, then negation,
+/// then multiplication and division, then addition and subtraction, which are left-associative.
+/// Unary operations are written `3!`, `√9`, `-3`, `⌊x⌋` and `⌈x⌉`,
+/// or `sqrt(9)`, `floor(x)` and `ceil(x)`.
+impl FromStr for BoundOp {
+    type Err = ParseExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+            end: s.len(),
+        };
+        if parser.tokens.is_empty() {
+            return Err(ParseExprError::new(ExprErrorKind::Empty, 0..s.len()));
+        }
+        let bop = parser.sum()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(bop),
+            Some((Token::Close(c), span)) => Err(ParseExprError::new(
+                ExprErrorKind::Unopened(*c),
+                span.clone(),
+            )),
+            Some((_, span)) => Err(ParseExprError::new(
+                ExprErrorKind::ExpectedOperator,
+                span.clone(),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Num(u8),
+    Op(Op),
+    Fact,
+    /// A prefix operation, such as `√` or `floor`
+    Func(UnaryOp),
+    Open(char),
+    Close(char),
+}
+
+/// The closing bracket of each opening bracket
+const BRACKETS: [(char, char); 3] = [('(', ')'), ('⌊', '⌋'), ('⌈', '⌉')];
+
+fn tokenize(s: &str) -> Result<Vec<(Token, Range<usize>)>, ParseExprError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let mut end = i + c.len_utf8();
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '0'..='9' => {
+                while let Some((j, '0'..='9')) = chars.peek() {
+                    end = j + 1;
+                    chars.next();
+                }
+                let n = s[i..end]
+                    .parse()
+                    .map_err(|_| ParseExprError::new(ExprErrorKind::NumberTooLarge, i..end))?;
+                Token::Num(n)
+            }
+            'a'..='z' | 'A'..='Z' => {
+                while let Some((j, c)) = chars.peek() {
+                    if !c.is_ascii_alphabetic() {
+                        break;
+                    }
+                    end = j + 1;
+                    chars.next();
+                }
+                let op = match s[i..end].to_lowercase().as_str() {
+                    "sqrt" => UnaryOp::Sqrt,
+                    "floor" => UnaryOp::Floor,
+                    "ceil" => UnaryOp::Ceil,
+                    name => {
+                        return Err(ParseExprError::new(
+                            ExprErrorKind::UnknownName(name.to_string()),
+                            i..end,
+                        ))
+                    }
+                };
+                Token::Func(op)
+            }
+            '*' if matches!(chars.peek(), Some((_, '*'))) => {
+                chars.next();
+                end += 1;
+                Token::Op(Op::Pow)
+            }
+            '−' => Token::Op(Op::Sub),
+            '!' => Token::Fact,
+            '√' => Token::Func(UnaryOp::Sqrt),
+            c if BRACKETS.iter().any(|(open, _)| *open == c) => Token::Open(c),
+            c if BRACKETS.iter().any(|(_, close)| *close == c) => Token::Close(c),
+            c => Token::Op(
+                Op::from_symbol(c)
+                    .ok_or_else(|| ParseExprError::new(ExprErrorKind::UnknownSymbol(c), i..end))?,
+            ),
+        };
+        tokens.push((token, i..end));
+    }
+    Ok(tokens)
+}
+
+/// A recursive descent parser with a function per level of precedence
+struct Parser {
+    tokens: Vec<(Token, Range<usize>)>,
+    pos: usize,
+    /// The length of the input, where errors at the end point
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.pos).map(|(token, _)| *token)
+    }
+
+    /// The span of the next token, or the empty span at the end of the input
+    fn span(&self) -> Range<usize> {
+        self.tokens
+            .get(self.pos)
+            .map_or(self.end..self.end, |(_, span)| span.clone())
+    }
+
+    /// Parses operands separated by any of `ops`, grouping from the left
+    fn left_assoc(
+        &mut self,
+        ops: &[Op],
+        operand: fn(&mut Self) -> Result<BoundOp, ParseExprError>,
+    ) -> Result<BoundOp, ParseExprError> {
+        let mut l = operand(self)?;
+        while let Some(Token::Op(op)) = self.peek() {
+            if !ops.contains(&op) {
+                break;
+            }
+            self.pos += 1;
+            let r = operand(self)?;
+            l = BoundOp::BoundOp {
+                op,
+                l: Box::new(l),
+                r: Box::new(r),
+            };
+        }
+        Ok(l)
+    }
+
+    fn sum(&mut self) -> Result<BoundOp, ParseExprError> {
+        self.left_assoc(&[Op::Add, Op::Sub], Self::product)
+    }
+
+    fn product(&mut self) -> Result<BoundOp, ParseExprError> {
+        self.left_assoc(&[Op::Mul, Op::Div], Self::negation)
+    }
+
+    /// `-2^2` is `-(2^2)`, as usual
+    fn negation(&mut self) -> Result<BoundOp, ParseExprError> {
+        if self.peek() != Some(Token::Op(Op::Sub)) {
+            return self.power();
+        }
+        self.pos += 1;
+        Ok(BoundOp::Unary {
+            op: UnaryOp::Neg,
+            arg: Box::new(self.negation()?),
+        })
+    }
+
+    /// Exponents may be negated, as in `2^-1`, and group from the right
+    fn power(&mut self) -> Result<BoundOp, ParseExprError> {
+        let base = self.factorial()?;
+        if self.peek() != Some(Token::Op(Op::Pow)) {
+            return Ok(base);
+        }
+        self.pos += 1;
+        Ok(BoundOp::BoundOp {
+            op: Op::Pow,
+            l: Box::new(base),
+            r: Box::new(self.negation()?),
+        })
+    }
+
+    fn factorial(&mut self) -> Result<BoundOp, ParseExprError> {
+        let mut arg = self.operand()?;
+        while self.peek() == Some(Token::Fact) {
+            self.pos += 1;
+            arg = BoundOp::Unary {
+                op: UnaryOp::Fact,
+                arg: Box::new(arg),
+            };
+        }
+        Ok(arg)
+    }
+
+    /// A number, a bracketed expression, or a prefix operation, which applies to the operand after it
+    fn operand(&mut self) -> Result<BoundOp, ParseExprError> {
+        let span = self.span();
+        let Some(token) = self.peek() else {
+            return Err(ParseExprError::new(ExprErrorKind::ExpectedOperand, span));
+        };
+        self.pos += 1;
+        match token {
+            Token::Num(n) => Ok(BoundOp::Val(n)),
+            Token::Func(op) => Ok(BoundOp::Unary {
+                op,
+                arg: Box::new(self.func_arg()?),
+            }),
+            Token::Open(open) => {
+                let inner = self.sum()?;
+                let (_, close) = BRACKETS.into_iter().find(|(o, _)| *o == open).unwrap();
+                match self.peek() {
+                    Some(Token::Close(c)) if c == close => self.pos += 1,
+                    None | Some(Token::Close(_)) => {
+                        return Err(ParseExprError::new(ExprErrorKind::Unclosed(open), span))
+                    }
+                    Some(_) => {
+                        return Err(ParseExprError::new(
+                            ExprErrorKind::ExpectedOperator,
+                            self.span(),
+                        ))
+                    }
+                }
+                Ok(match open {
+                    '⌊' => BoundOp::Unary {
+                        op: UnaryOp::Floor,
+                        arg: Box::new(inner),
+                    },
+                    '⌈' => BoundOp::Unary {
+                        op: UnaryOp::Ceil,
+                        arg: Box::new(inner),
+                    },
+                    _ => inner,
+                })
+            }
+            Token::Op(_) | Token::Fact | Token::Close(_) => {
+                Err(ParseExprError::new(ExprErrorKind::ExpectedOperand, span))
+            }
+        }
+    }
+
+    /// The operand of a prefix operation, which may be negated like an exponent, as in `√-4`
+    fn func_arg(&mut self) -> Result<BoundOp, ParseExprError> {
+        if self.peek() != Some(Token::Op(Op::Sub)) {
+            return self.operand();
+        }
+        self.pos += 1;
+        Ok(BoundOp::Unary {
+            op: UnaryOp::Neg,
+            arg: Box::new(self.func_arg()?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+    use crate::{Cards, Ops};
+
+    fn parse(s: &str) -> Result<String, ParseExprError> {
+        s.parse::<BoundOp>().map(|bop| bop.to_string())
+    }
+
+    fn err(s: &str) -> (ExprErrorKind, Range<usize>) {
+        let e = s.parse::<BoundOp>().unwrap_err();
+        (e.kind, e.span)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("8÷(3-8÷3)").unwrap(), "(8÷(3-(8÷3)))");
+        assert_eq!(parse(" 8 / ( 3 - 8 / 3 ) ").unwrap(), "(8÷(3-(8÷3)))");
+        assert_eq!(parse("1+2*3-4").unwrap(), "((1+(2×3))-4)");
+        assert_eq!(parse("1-2-3").unwrap(), "((1-2)-3)");
+        assert_eq!(parse("12÷2÷3").unwrap(), "((12÷2)÷3)");
+        assert_eq!(parse("2^3^2").unwrap(), "(2^(3^2))");
+        assert_eq!(parse("2**3*4").unwrap(), "((2^3)×4)");
+        assert_eq!(parse("-2^2").unwrap(), "(-(2^2))");
+        assert_eq!(parse("2^-1").unwrap(), "(2^(-1))");
+        assert_eq!(parse("√-4").unwrap(), parse("√(-4)").unwrap());
+        assert_eq!(parse("sqrt -(1+3)×2").unwrap(), "(√((-(1+3)))×2)");
+        assert_eq!(parse("4 − 1").unwrap(), "(4-1)");
+        assert_eq!(parse("3!!").unwrap(), "(3!)!");
+        assert_eq!(parse("√9+⌊7÷2⌋×⌈1÷2⌉").unwrap(), "(√9+(⌊(7÷2)⌋×⌈(1÷2)⌉))");
+        assert_eq!(parse("((24))").unwrap(), "24");
+        assert_eq!(
+            parse("sqrt(9)*floor(7/2)+Ceil 3").unwrap(),
+            "((√9×⌊(7÷2)⌋)+⌈3⌉)"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(err(""), (ExprErrorKind::Empty, 0..0));
+        assert_eq!(err("  "), (ExprErrorKind::Empty, 0..2));
+        assert_eq!(err("1+#"), (ExprErrorKind::UnknownSymbol('#'), 2..3));
+        assert_eq!(
+            err("1+log(2)"),
+            (ExprErrorKind::UnknownName("log".to_string()), 2..5)
+        );
+        assert_eq!(err("1×256"), (ExprErrorKind::NumberTooLarge, 3..6));
+        assert_eq!(err("1+"), (ExprErrorKind::ExpectedOperand, 2..2));
+        assert_eq!(err("1+*2"), (ExprErrorKind::ExpectedOperand, 2..3));
+        assert_eq!(err("√-"), (ExprErrorKind::ExpectedOperand, 4..4));
+        assert_eq!(err("1 2"), (ExprErrorKind::ExpectedOperator, 2..3));
+        assert_eq!(err("(1+2)(3)"), (ExprErrorKind::ExpectedOperator, 5..6));
+        assert_eq!(err("(1+2 3)"), (ExprErrorKind::ExpectedOperator, 5..6));
+        assert_eq!(err("2×(1+2"), (ExprErrorKind::Unclosed('('), 3..4));
+        assert_eq!(err("⌊1+2)"), (ExprErrorKind::Unclosed('⌊'), 0..3));
+        assert_eq!(err("1+2)"), (ExprErrorKind::Unopened(')'), 3..4));
+        assert_eq!(
+            "1+".parse::<BoundOp>().unwrap_err().to_string(),
+            "expected a number or `(` at 2..2"
+        );
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        let ops = Ops::default()
+            .with_unary(UnaryOp::Fact)
+            .with_unary(UnaryOp::Sqrt)
+            .with_unary(UnaryOp::Neg)
+            .with_unary(UnaryOp::Floor)
+            .with_unary(UnaryOp::Ceil);
+        for cards in [vec![3, 3, 8, 8], vec![1, 2, 3, 4]] {
+            for sol in Cards::with_ops(cards, ops.clone()).solve().unwrap() {
+                let s = sol.to_string();
+                assert_eq!(parse(&s).unwrap(), s);
+            }
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use crate::{BoundOp, Op, Step, StepOp, UnaryOp, Value};
+
+/// Which symbols [`BoundOp::format`] writes operations with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Symbols {
+    /// `×`, `÷`, `√` and the floor and ceiling brackets, as [`Op`] and [`UnaryOp`] are displayed
+    #[default]
+    Unicode,
+    /// `*`, `/`, `sqrt(x)`, `floor(x)` and `ceil(x)`
+    Ascii,
+}
+
+/// How [`BoundOp::format`] writes expressions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    symbols: Symbols,
+    spaces: bool,
+}
+
+impl Style {
+    /// Unicode symbols without spaces, such as `8÷(3-8÷3)`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specify the symbols of operations
+    pub fn with_symbols(mut self, symbols: Symbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Put spaces around binary operators other than `^`, such as `8 ÷ (3 - 8 ÷ 3)`
+    pub fn with_spaces(mut self, spaces: bool) -> Self {
+        self.spaces = spaces;
+        self
+    }
+}
+
+/// How tightly an expression binds to its neighbours
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Prec {
+    Sum,
+    Product,
+    Negation,
+    Power,
+    /// Factorials and square roots
+    Unary,
+    /// Numbers and bracketed expressions, which never need parentheses
+    Atom,
+}
+
+pub(crate) fn prec(bop: &BoundOp) -> Prec {
+    match bop {
+        BoundOp::Val(_) => Prec::Atom,
+        BoundOp::BoundOp { op, .. } => match op {
+            Op::Add | Op::Sub => Prec::Sum,
+            Op::Mul | Op::Div => Prec::Product,
+            Op::Pow => Prec::Power,
+        },
+        BoundOp::Unary { op, .. } => match op {
+            UnaryOp::Neg => Prec::Negation,
+            UnaryOp::Fact | UnaryOp::Sqrt => Prec::Unary,
+            UnaryOp::Floor | UnaryOp::Ceil => Prec::Atom,
+        },
+    }
+}
+
+impl BoundOp {
+    /// Writes the expression with only the parentheses that precedence and associativity require.
+    ///
+    /// `+`, `-`, `×` and `÷` group from the left and `^` groups from the right, as
+    /// [`BoundOp::from_str`](std::str::FromStr) reads them, so an operand on the other side
+    /// keeps its parentheses even when the operation is associative, as in `1+(2+3)`.
+    /// Negations on the right of binary operations are parenthesized, as in `3-(-2)`, and so are
+    /// the operands of factorials and square roots unless they are numbers or brackets.
+    pub fn format(&self, style: Style) -> String {
+        let mut s = String::new();
+        self.write(style, &mut s);
+        s
+    }
+}
+
+impl Step {
+    /// Writes the step as a line of working, such as `3-8/3 = 1/3`, with its operands
+    /// parenthesized as [`BoundOp::format`] would parenthesize the same expression.
+    ///
+    /// Fractions are written with `/` and group like a division, so `8÷(1/3)` keeps its
+    /// parentheses, and negative values group like a negation.
+    pub fn format(&self, style: Style) -> String {
+        let mut s = String::new();
+        match self.op {
+            StepOp::Binary { op, rhs } => write_binary(op, &self.lhs, &rhs, style, &mut s),
+            StepOp::Unary(op) => write_unary(op, &self.lhs, style, &mut s),
+        }
+        s.push_str(" = ");
+        s.push_str(&self.result.to_string());
+        s
+    }
+}
+
+/// Something that is written as the operand of an operation
+trait Operand {
+    fn prec(&self) -> Prec;
+
+    fn write(&self, style: Style, s: &mut String);
+}
+
+impl Operand for BoundOp {
+    fn prec(&self) -> Prec {
+        prec(self)
+    }
+
+    fn write(&self, style: Style, s: &mut String) {
+        match self {
+            BoundOp::Val(n) => s.push_str(&n.to_string()),
+            BoundOp::BoundOp { op, l, r } => write_binary(*op, &**l, &**r, style, s),
+            BoundOp::Unary { op, arg } => write_unary(*op, &**arg, style, s),
+        }
+    }
+}
+
+impl Operand for Value {
+    fn prec(&self) -> Prec {
+        match self {
+            Value::Exact(v) if v.numer() < 0 => Prec::Negation,
+            Value::Exact(v) if !v.is_integer() => Prec::Product,
+            Value::Approx(v) if *v < 0.0 => Prec::Negation,
+            _ => Prec::Atom,
+        }
+    }
+
+    fn write(&self, _: Style, s: &mut String) {
+        s.push_str(&self.to_string());
+    }
+}
+
+fn write_binary(op: Op, l: &impl Operand, r: &impl Operand, style: Style, s: &mut String) {
+    let (prec_l, prec_r) = (l.prec(), r.prec());
+    let (paren_l, paren_r) = if op == Op::Pow {
+        (prec_l <= Prec::Power, prec_r < Prec::Power)
+    } else {
+        let parent = match op {
+            Op::Add | Op::Sub => Prec::Sum,
+            _ => Prec::Product,
+        };
+        (
+            prec_l < parent,
+            prec_r <= parent || prec_r == Prec::Negation,
+        )
+    };
+    write_operand(l, paren_l, style, s);
+    let symbol = match (op, style.symbols) {
+        (Op::Mul, Symbols::Ascii) => '*',
+        (Op::Div, Symbols::Ascii) => '/',
+        _ => op.to_string().chars().next().unwrap(),
+    };
+    if style.spaces && op != Op::Pow {
+        s.push(' ');
+        s.push(symbol);
+        s.push(' ');
+    } else {
+        s.push(symbol);
+    }
+    write_operand(r, paren_r, style, s);
+}
+
+fn write_unary(op: UnaryOp, arg: &impl Operand, style: Style, s: &mut String) {
+    let atom = arg.prec() == Prec::Atom;
+    match (op, style.symbols) {
+        (UnaryOp::Neg, _) => {
+            s.push('-');
+            write_operand(arg, arg.prec() <= Prec::Negation, style, s);
+        }
+        (UnaryOp::Fact, _) => {
+            write_operand(arg, !atom, style, s);
+            s.push('!');
+        }
+        (UnaryOp::Sqrt, Symbols::Unicode) => {
+            s.push('√');
+            write_operand(arg, !atom, style, s);
+        }
+        (UnaryOp::Floor, Symbols::Unicode) => write_bracketed(arg, '⌊', '⌋', style, s),
+        (UnaryOp::Ceil, Symbols::Unicode) => write_bracketed(arg, '⌈', '⌉', style, s),
+        (UnaryOp::Sqrt, Symbols::Ascii) => {
+            s.push_str("sqrt");
+            write_bracketed(arg, '(', ')', style, s);
+        }
+        (UnaryOp::Floor, Symbols::Ascii) => {
+            s.push_str("floor");
+            write_bracketed(arg, '(', ')', style, s);
+        }
+        (UnaryOp::Ceil, Symbols::Ascii) => {
+            s.push_str("ceil");
+            write_bracketed(arg, '(', ')', style, s);
+        }
+    }
+}
+
+fn write_operand(arg: &impl Operand, paren: bool, style: Style, s: &mut String) {
+    if paren {
+        write_bracketed(arg, '(', ')', style, s);
+    } else {
+        arg.write(style, s);
+    }
+}
+
+fn write_bracketed(arg: &impl Operand, open: char, close: char, style: Style, s: &mut String) {
+    s.push(open);
+    arg.write(style, s);
+    s.push(close);
+}
+
+#[cfg(test)]
+mod pretty_tests {
+    use super::*;
+    use crate::{Cards, Ops};
+
+    fn format(s: &str, style: Style) -> String {
+        s.parse::<BoundOp>().unwrap().format(style)
+    }
+
+    #[test]
+    fn test_format() {
+        let style = Style::new();
+        assert_eq!(format("(8÷(3-(8÷3)))", style), "8÷(3-8÷3)");
+        assert_eq!(format("((1+(2+3))×4)", style), "(1+(2+3))×4");
+        assert_eq!(format("((1×2)×(3×4))", style), "1×2×(3×4)");
+        assert_eq!(format("((1+2)-(3-4))", style), "1+2-(3-4)");
+        assert_eq!(format("((1×2)+(3÷4))", style), "1×2+3÷4");
+        assert_eq!(format("((2^3)^2)", style), "(2^3)^2");
+        assert_eq!(format("(2^(3^2))", style), "2^3^2");
+        assert_eq!(format("((2×3)^2)", style), "(2×3)^2");
+        assert_eq!(format("(2^(-1))", style), "2^(-1)");
+        assert_eq!(format("(3-(-2))", style), "3-(-2)");
+        assert_eq!(format("(-(2^2))", style), "-2^2");
+        assert_eq!(format("((-2)^2)", style), "(-2)^2");
+        assert_eq!(format("(-(-2))", style), "-(-2)");
+        assert_eq!(format("(3!)!", style), "(3!)!");
+        assert_eq!(format("√(1+3)×3!", style), "√(1+3)×3!");
+        assert_eq!(format("((3!)^(√4))", style), "3!^√4");
+        assert_eq!(format("⌊(7÷2)⌋", style), "⌊7÷2⌋");
+    }
+
+    #[test]
+    fn test_format_style() {
+        let ascii = Style::new().with_symbols(Symbols::Ascii);
+        assert_eq!(format("(8÷(3-(8÷3)))", ascii), "8/(3-8/3)");
+        assert_eq!(format("(√(2×8)×⌈(5÷2)⌉)", ascii), "sqrt(2*8)*ceil(5/2)");
+        assert_eq!(format("⌊√9⌋", ascii), "floor(sqrt(9))");
+
+        let spaced = Style::new().with_spaces(true);
+        assert_eq!(format("((1+(2+3))×4)", spaced), "(1 + (2 + 3)) × 4");
+        assert_eq!(format("((2^3)×(4-1))", spaced), "2^3 × (4 - 1)");
+        assert_eq!(
+            format("(8÷(3-(8÷3)))", spaced.with_symbols(Symbols::Ascii)),
+            "8 / (3 - 8 / 3)"
+        );
+    }
+
+    #[test]
+    fn test_format_round_trip() {
+        let ops = Ops::default()
+            .with_unary(UnaryOp::Fact)
+            .with_unary(UnaryOp::Sqrt)
+            .with_unary(UnaryOp::Neg)
+            .with_unary(UnaryOp::Floor)
+            .with_unary(UnaryOp::Ceil);
+        let styles = [
+            Style::new(),
+            Style::new().with_spaces(true),
+            Style::new().with_symbols(Symbols::Ascii),
+        ];
+        for cards in [vec![3, 3, 8, 8], vec![1, 2, 3, 4]] {
+            for sol in Cards::with_ops(cards, ops.clone()).solve().unwrap() {
+                for style in styles {
+                    let s = sol.format(style);
+                    let parsed = s.parse::<BoundOp>().unwrap();
+                    assert_eq!(parsed.to_string(), sol.to_string(), "{s}");
+                }
+            }
+        }
+    }
+
+    fn format_steps(s: &str, style: Style) -> Vec<String> {
+        let bop = s.parse::<BoundOp>().unwrap();
+        bop.explain()
+            .iter()
+            .map(|step| step.format(style))
+            .collect()
+    }
+
+    #[test]
+    fn test_format_steps() {
+        let style = Style::new();
+        assert_eq!(
+            format_steps("8÷(3-8÷3)", style),
+            ["8÷3 = 8/3", "3-8/3 = 1/3", "8÷(1/3) = 24"]
+        );
+        assert_eq!(
+            format_steps("(3-8)×(2-7)-1", style),
+            ["3-8 = -5", "2-7 = -5", "-5×(-5) = 25", "25-1 = 24"]
+        );
+        assert_eq!(
+            format_steps("(1÷2)^2×(-(1-3))!", style),
+            [
+                "1÷2 = 1/2",
+                "(1/2)^2 = 1/4",
+                "1-3 = -2",
+                "-(-2) = 2",
+                "2! = 2",
+                "1/4×2 = 1/2"
+            ]
+        );
+        assert_eq!(
+            format_steps(
+                "8÷(3-8÷3)",
+                Style::new().with_spaces(true).with_symbols(Symbols::Ascii)
+            ),
+            ["8 / 3 = 8/3", "3 - 8/3 = 1/3", "8 / (1/3) = 24"]
+        );
+        assert_eq!(format_steps("√2", style), ["√2 = 1.4142135623730951"]);
+    }
+}
@@ -0,0 +1,384 @@
+use std::{cmp::Ordering, fmt};
+
+/// An exact fraction, always kept in lowest terms with a positive denominator.
+///
+/// Arithmetic is checked: operations that would overflow `i128`, divide by zero
+/// or leave the rationals return `None`.
+///
+/// With the `serde` feature, it is serialized as a string such as `"8/3"` or `"24"`, since its
+/// numerator and denominator may be too large for a JSON number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+impl Rational {
+    pub const ZERO: Self = Self { num: 0, den: 1 };
+    pub const ONE: Self = Self { num: 1, den: 1 };
+
+    /// Creates a reduced fraction. Returns `None` if the denominator is zero.
+    pub fn new(num: i128, den: i128) -> Option<Self> {
+        if den == 0 {
+            return None;
+        }
+        let g = gcd(num.unsigned_abs(), den.unsigned_abs());
+        let (mut num, mut den) = if g == 1 {
+            (num, den)
+        } else {
+            (num / g as i128, den / g as i128)
+        };
+        if den < 0 {
+            num = num.checked_neg()?;
+            den = den.checked_neg()?;
+        }
+        Some(Self { num, den })
+    }
+
+    pub fn from_integer(n: i128) -> Self {
+        Self { num: n, den: 1 }
+    }
+
+    /// Converts a float without rounding.
+    ///
+    /// Every finite `f64` is a dyadic fraction, so the conversion is exact as long as it fits
+    /// in `i128`. Returns `None` for NaN, infinities and values that do not fit.
+    pub fn from_f64(x: f64) -> Option<Self> {
+        if !x.is_finite() {
+            return None;
+        }
+        if x == 0.0 {
+            return Some(Self::ZERO);
+        }
+        let bits = x.to_bits();
+        let sign = if bits >> 63 == 0 { 1 } else { -1 };
+        let exp = ((bits >> 52) & 0x7ff) as i32;
+        let mantissa = if exp == 0 {
+            (bits & 0xf_ffff_ffff_ffff) << 1
+        } else {
+            (bits & 0xf_ffff_ffff_ffff) | 0x10_0000_0000_0000
+        } as i128;
+        // x = sign * mantissa * 2^exp
+        let exp = exp - 1075;
+        let tz = mantissa.trailing_zeros() as i32;
+        let (mantissa, exp) = (mantissa >> tz, exp + tz);
+        if exp >= 0 {
+            let num = mantissa.checked_mul(1i128.checked_shl(exp as u32)?)?;
+            if num >> exp != mantissa {
+                return None;
+            }
+            Some(Self::from_integer(sign * num))
+        } else if exp > -127 {
+            Self::new(sign * mantissa, 1 << -exp)
+        } else {
+            None
+        }
+    }
+
+    pub fn numer(&self) -> i128 {
+        self.num
+    }
+
+    pub fn denom(&self) -> i128 {
+        self.den
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.den == 1
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        if self.den == 1 && rhs.den == 1 {
+            return Some(Self::from_integer(self.num.checked_add(rhs.num)?));
+        }
+        let g = gcd(self.den as u128, rhs.den as u128) as i128;
+        let num =
+            (self.num.checked_mul(rhs.den / g)?).checked_add(rhs.num.checked_mul(self.den / g)?)?;
+        Self::new(num, (self.den / g).checked_mul(rhs.den)?)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.checked_add(rhs.checked_neg()?)
+    }
+
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        if self.den == 1 && rhs.den == 1 {
+            return Some(Self::from_integer(self.num.checked_mul(rhs.num)?));
+        }
+        // Cross-reduce first to keep intermediates small
+        let g1 = gcd(self.num.unsigned_abs(), rhs.den as u128) as i128;
+        let g2 = gcd(rhs.num.unsigned_abs(), self.den as u128) as i128;
+        let num = (self.num / g1).checked_mul(rhs.num / g2)?;
+        let den = (self.den / g2).checked_mul(rhs.den / g1)?;
+        Self::new(num, den)
+    }
+
+    /// Returns `None` when dividing by zero.
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        self.checked_mul(rhs.checked_recip()?)
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        Some(Self {
+            num: self.num.checked_neg()?,
+            den: self.den,
+        })
+    }
+
+    /// Returns `None` for zero.
+    pub fn checked_recip(self) -> Option<Self> {
+        Self::new(self.den, self.num)
+    }
+
+    /// Raises `self` to a rational power.
+    ///
+    /// A fractional exponent `p/q` only has a rational result when `self` is a perfect `q`th
+    /// power, e.g. `8^(1/3) = 2`. Returns `None` otherwise.
+    pub fn checked_pow(self, exp: Self) -> Option<Self> {
+        let base = if exp.den == 1 {
+            self
+        } else {
+            let k = u32::try_from(exp.den).ok()?;
+            if self.num < 0 && k % 2 == 0 {
+                return None;
+            }
+            let num = iroot(self.num.unsigned_abs(), k)? as i128;
+            let num = if self.num < 0 { -num } else { num };
+            Self {
+                num,
+                den: iroot(self.den as u128, k)? as i128,
+            }
+        };
+        let p = u32::try_from(exp.num.unsigned_abs()).ok()?;
+        // Anything but 0 and ±1 overflows long before this
+        if p >= 128 && (base.num.unsigned_abs() > 1 || base.den > 1) {
+            return None;
+        }
+        let pow = Self {
+            num: base.num.checked_pow(p)?,
+            den: base.den.checked_pow(p)?,
+        };
+        if exp.num < 0 {
+            pow.checked_recip()
+        } else {
+            Some(pow)
+        }
+    }
+}
+
+impl From<u8> for Rational {
+    fn from(n: u8) -> Self {
+        Self::from_integer(n as i128)
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Compare integer parts, then the reciprocals of the fractional parts,
+        // which avoids overflowing cross-multiplication.
+        let (a, b) = (
+            self.num.div_euclid(self.den),
+            other.num.div_euclid(other.den),
+        );
+        if a != b {
+            return a.cmp(&b);
+        }
+        let (ra, rb) = (
+            self.num.rem_euclid(self.den),
+            other.num.rem_euclid(other.den),
+        );
+        match (ra, rb) {
+            (0, 0) => Ordering::Equal,
+            (0, _) => Ordering::Less,
+            (_, 0) => Ordering::Greater,
+            _ => Self {
+                num: other.den,
+                den: rb,
+            }
+            .cmp(&Self {
+                num: self.den,
+                den: ra,
+            }),
+        }
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Rational {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Rational {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let (num, den) = s.split_once('/').unwrap_or((&s, "1"));
+        num.parse()
+            .ok()
+            .zip(den.parse().ok())
+            .and_then(|(num, den)| Self::new(num, den))
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid fraction `{s}`")))
+    }
+}
+
+/// Binary GCD, which avoids slow 128-bit division. Returns 1 when both are zero.
+fn gcd(a: u128, b: u128) -> u128 {
+    if a == 0 || b == 0 {
+        return (a | b).max(1);
+    }
+    if a | b <= u64::MAX as u128 {
+        return gcd_u64(a as u64, b as u64) as u128;
+    }
+    let shift = (a | b).trailing_zeros();
+    let (mut a, mut b) = (a >> a.trailing_zeros(), b);
+    loop {
+        b >>= b.trailing_zeros();
+        if a > b {
+            (a, b) = (b, a);
+        }
+        b -= a;
+        if b == 0 {
+            return a << shift;
+        }
+    }
+}
+
+fn gcd_u64(a: u64, b: u64) -> u64 {
+    let shift = (a | b).trailing_zeros();
+    let (mut a, mut b) = (a >> a.trailing_zeros(), b);
+    loop {
+        b >>= b.trailing_zeros();
+        if a > b {
+            (a, b) = (b, a);
+        }
+        b -= a;
+        if b == 0 {
+            return a << shift;
+        }
+    }
+}
+
+/// The exact `k`th root of `n`, if there is one.
+fn iroot(n: u128, k: u32) -> Option<u128> {
+    if n < 2 || k == 1 {
+        return Some(n);
+    }
+    // A float estimate is within one of the root while the root is small
+    let estimate = (n as f64).powf(1.0 / k as f64).round();
+    if estimate < (1u64 << 52) as f64 {
+        let estimate = estimate as u128;
+        return (estimate.saturating_sub(1)..=estimate + 1).find(|r| r.checked_pow(k) == Some(n));
+    }
+    let (mut lo, mut hi) = (1u128, 1u128 << (128 / k + 1).min(127));
+    while lo <= hi {
+        let mid = lo + (hi - lo) / 2;
+        match mid.checked_pow(k) {
+            Some(p) if p == n => return Some(mid),
+            Some(p) if p < n => lo = mid + 1,
+            _ => hi = mid - 1,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod rational_tests {
+    use super::*;
+
+    fn r(num: i128, den: i128) -> Rational {
+        Rational::new(num, den).unwrap()
+    }
+
+    #[test]
+    fn test_new_reduces() {
+        assert_eq!(r(6, -4), r(-3, 2));
+        assert_eq!(r(-6, -4).numer(), 3);
+        assert_eq!(r(-6, -4).denom(), 2);
+        assert_eq!(Rational::new(1, 0), None);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let third = r(8, 1).checked_div(r(3, 1)).unwrap();
+        let diff = r(3, 1).checked_sub(third).unwrap();
+        assert_eq!(diff, r(1, 3));
+        assert_eq!(r(8, 1).checked_div(diff), Some(r(24, 1)));
+        assert_eq!(r(1, 2).checked_add(r(1, 3)), Some(r(5, 6)));
+        assert_eq!(r(2, 3).checked_mul(r(3, 4)), Some(r(1, 2)));
+        assert_eq!(r(1, 1).checked_div(Rational::ZERO), None);
+    }
+
+    #[test]
+    fn test_pow() {
+        assert_eq!(r(2, 3).checked_pow(r(3, 1)), Some(r(8, 27)));
+        assert_eq!(r(2, 1).checked_pow(r(-2, 1)), Some(r(1, 4)));
+        assert_eq!(r(8, 27).checked_pow(r(2, 3)), Some(r(4, 9)));
+        assert_eq!(r(-8, 1).checked_pow(r(1, 3)), Some(r(-2, 1)));
+        assert_eq!(r(2, 1).checked_pow(r(1, 2)), None);
+        assert_eq!(r(-4, 1).checked_pow(r(1, 2)), None);
+        assert_eq!(Rational::ZERO.checked_pow(r(-1, 1)), None);
+        assert_eq!(r(13, 1).checked_pow(r(100, 1)), None);
+    }
+
+    #[test]
+    fn test_cmp() {
+        assert!(r(1, 3) < r(1, 2));
+        assert!(r(-1, 2) < r(-1, 3));
+        assert!(r(7, 2) > r(3, 1));
+        assert!(r(i128::MAX, 3) > r(i128::MAX - 1, 3));
+        assert_eq!(r(4, 6).cmp(&r(2, 3)), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_from_f64() {
+        assert_eq!(Rational::from_f64(24.0), Some(r(24, 1)));
+        assert_eq!(Rational::from_f64(-2.5), Some(r(-5, 2)));
+        assert_eq!(Rational::from_f64(0.375), Some(r(3, 8)));
+        assert_eq!(Rational::from_f64(f64::NAN), None);
+        assert_eq!(Rational::from_f64(1e300), None);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(r(-1, 3).to_string(), "-1/3");
+        assert_eq!(r(24, 1).to_string(), "24");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        assert_eq!(serde_json::to_string(&r(-8, 3)).unwrap(), r#""-8/3""#);
+        assert_eq!(
+            serde_json::from_str::<Rational>(r#""6/-4""#).unwrap(),
+            r(-3, 2)
+        );
+        assert_eq!(
+            serde_json::from_str::<Rational>(r#""24""#).unwrap(),
+            r(24, 1)
+        );
+        assert!(serde_json::from_str::<Rational>(r#""1/0""#).is_err());
+        assert!(serde_json::from_str::<Rational>("24").is_err());
+    }
+}
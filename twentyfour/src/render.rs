@@ -0,0 +1,472 @@
+use crate::{
+    pretty::{prec, Prec},
+    BoundOp, Op, Step, StepOp, UnaryOp, Value,
+};
+use std::fmt;
+
+/// A presentation MathML element, so that formulas can be built without parsing markup.
+///
+/// It is displayed as markup, such as `<mfrac><mn>8</mn><mn>3</mn></mfrac>`, with `&`, `<` and
+/// `>` escaped in the text of tokens. With the `serde`
+/// feature, it is serialized with one key per element, named after its tag: `{"mn": "8"}`,
+/// `{"mrow": [...]}`, `{"mfrac": [numerator, denominator]}` or `{"msqrt": ...}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum MathMl {
+    /// A whole formula
+    Math(Box<MathMl>),
+    Mrow(Vec<MathMl>),
+    /// A number
+    Mn(String),
+    /// An operator or a bracket
+    Mo(String),
+    Mfrac(Box<MathMl>, Box<MathMl>),
+    Msup(Box<MathMl>, Box<MathMl>),
+    Msqrt(Box<MathMl>),
+}
+
+impl MathMl {
+    fn mo(op: &str) -> Self {
+        MathMl::Mo(op.to_string())
+    }
+
+    fn row(children: impl Into<Vec<MathMl>>) -> Self {
+        MathMl::Mrow(children.into())
+    }
+
+    fn frac(numer: MathMl, denom: MathMl) -> Self {
+        MathMl::Mfrac(Box::new(numer), Box::new(denom))
+    }
+
+    fn parenthesized(self) -> Self {
+        MathMl::row([MathMl::mo("("), self, MathMl::mo(")")])
+    }
+}
+
+impl fmt::Display for MathMl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MathMl::Math(row) => write!(f, "<math>{row}</math>"),
+            MathMl::Mrow(children) => {
+                write!(f, "<mrow>")?;
+                for child in children {
+                    write!(f, "{child}")?;
+                }
+                write!(f, "</mrow>")
+            }
+            MathMl::Mn(n) => write!(f, "<mn>{}</mn>", Escaped(n)),
+            MathMl::Mo(op) => write!(f, "<mo>{}</mo>", Escaped(op)),
+            MathMl::Mfrac(numer, denom) => write!(f, "<mfrac>{numer}{denom}</mfrac>"),
+            MathMl::Msup(base, exp) => write!(f, "<msup>{base}{exp}</msup>"),
+            MathMl::Msqrt(arg) => write!(f, "<msqrt>{arg}</msqrt>"),
+        }
+    }
+}
+
+/// Text written with the characters that would start markup escaped
+struct Escaped<'a>(&'a str);
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '&' => write!(f, "&amp;")?,
+                '<' => write!(f, "&lt;")?,
+                '>' => write!(f, "&gt;")?,
+                c => write!(f, "{c}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Like [`prec`], but fractions and square roots are drawn around their operands,
+/// so they never need parentheses
+fn prec_2d(bop: &BoundOp) -> Prec {
+    match bop {
+        BoundOp::BoundOp { op: Op::Div, .. }
+        | BoundOp::Unary {
+            op: UnaryOp::Sqrt, ..
+        } => Prec::Atom,
+        _ => prec(bop),
+    }
+}
+
+/// Which operands of `bop` need parentheses, following the rules of [`BoundOp::format`]
+fn parens(bop: &BoundOp) -> (bool, bool) {
+    match bop {
+        BoundOp::Val(_) => (false, false),
+        BoundOp::BoundOp { op: Op::Div, .. } => (false, false),
+        // The exponent is set apart by its position
+        BoundOp::BoundOp { op: Op::Pow, l, .. } => (prec_2d(l) <= Prec::Unary, false),
+        BoundOp::BoundOp { l, r, .. } => {
+            let parent = prec(bop);
+            let prec_r = prec_2d(r);
+            (
+                prec_2d(l) < parent,
+                prec_r <= parent || prec_r == Prec::Negation,
+            )
+        }
+        BoundOp::Unary { op, arg } => match op {
+            UnaryOp::Neg => (prec_2d(arg) <= Prec::Negation, false),
+            UnaryOp::Fact => (prec(arg) != Prec::Atom, false),
+            UnaryOp::Sqrt | UnaryOp::Floor | UnaryOp::Ceil => (false, false),
+        },
+    }
+}
+
+impl BoundOp {
+    /// Writes the expression as LaTeX math, such as `\frac{8}{3 - \frac{8}{3}}`.
+    ///
+    /// Division is written as a fraction and exponentiation as a superscript.
+    pub fn to_latex(&self) -> String {
+        let (paren_l, paren_r) = parens(self);
+        match self {
+            BoundOp::Val(n) => n.to_string(),
+            BoundOp::BoundOp { op, l, r } => {
+                let (l, r) = (latex_operand(l, paren_l), latex_operand(r, paren_r));
+                match op {
+                    Op::Add => format!("{l} + {r}"),
+                    Op::Sub => format!("{l} - {r}"),
+                    Op::Mul => format!("{l} \\times {r}"),
+                    Op::Div => format!("\\frac{{{l}}}{{{r}}}"),
+                    Op::Pow => format!("{{{l}}}^{{{r}}}"),
+                }
+            }
+            BoundOp::Unary { op, arg } => {
+                let arg = latex_operand(arg, paren_l);
+                match op {
+                    UnaryOp::Fact => format!("{arg}!"),
+                    UnaryOp::Sqrt => format!("\\sqrt{{{arg}}}"),
+                    UnaryOp::Neg => format!("-{arg}"),
+                    UnaryOp::Floor => format!("\\left\\lfloor {arg} \\right\\rfloor"),
+                    UnaryOp::Ceil => format!("\\left\\lceil {arg} \\right\\rceil"),
+                }
+            }
+        }
+    }
+
+    /// Writes the expression as a presentation MathML `<math>` element.
+    ///
+    /// Division is written as `<mfrac>` and exponentiation as `<msup>`.
+    /// The text of every token is escaped, so the markup can be embedded in HTML as is.
+    pub fn to_mathml(&self) -> String {
+        self.to_mathml_element().to_string()
+    }
+
+    /// The `<math>` element of [`BoundOp::to_mathml`], before it is written as markup
+    pub fn to_mathml_element(&self) -> MathMl {
+        MathMl::Math(Box::new(self.mathml_row()))
+    }
+
+    /// The content of [`BoundOp::to_mathml`] without the `<math>` element,
+    /// for embedding in a larger formula such as an equation
+    pub fn to_mathml_row(&self) -> String {
+        self.mathml_row().to_string()
+    }
+
+    fn mathml_row(&self) -> MathMl {
+        let (paren_l, paren_r) = parens(self);
+        match self {
+            BoundOp::Val(n) => MathMl::Mn(n.to_string()),
+            BoundOp::BoundOp { op, l, r } => {
+                let (l, r) = (mathml_operand(l, paren_l), mathml_operand(r, paren_r));
+                match op {
+                    Op::Add => MathMl::row([l, MathMl::mo("+"), r]),
+                    Op::Sub => MathMl::row([l, MathMl::mo("−"), r]),
+                    Op::Mul => MathMl::row([l, MathMl::mo("×"), r]),
+                    Op::Div => MathMl::frac(l, r),
+                    Op::Pow => MathMl::Msup(Box::new(l), Box::new(r)),
+                }
+            }
+            BoundOp::Unary { op, arg } => {
+                let arg = mathml_operand(arg, paren_l);
+                match op {
+                    UnaryOp::Fact => MathMl::row([arg, MathMl::mo("!")]),
+                    UnaryOp::Sqrt => MathMl::Msqrt(Box::new(arg)),
+                    UnaryOp::Neg => MathMl::row([MathMl::mo("−"), arg]),
+                    UnaryOp::Floor => MathMl::row([MathMl::mo("⌊"), arg, MathMl::mo("⌋")]),
+                    UnaryOp::Ceil => MathMl::row([MathMl::mo("⌈"), arg, MathMl::mo("⌉")]),
+                }
+            }
+        }
+    }
+}
+
+fn latex_operand(bop: &BoundOp, paren: bool) -> String {
+    if paren {
+        format!("\\left({}\\right)", bop.to_latex())
+    } else {
+        bop.to_latex()
+    }
+}
+
+fn mathml_operand(bop: &BoundOp, paren: bool) -> MathMl {
+    if paren {
+        bop.mathml_row().parenthesized()
+    } else {
+        bop.mathml_row()
+    }
+}
+
+impl Value {
+    /// Writes the value as LaTeX, with fractions as `\frac`
+    pub fn to_latex(&self) -> String {
+        match self {
+            Value::Exact(v) if v.is_integer() => v.to_string(),
+            Value::Exact(v) if v.numer() < 0 => {
+                format!("-\\frac{{{}}}{{{}}}", -v.numer(), v.denom())
+            }
+            Value::Exact(v) => format!("\\frac{{{}}}{{{}}}", v.numer(), v.denom()),
+            Value::Approx(v) => v.to_string(),
+            Value::Undefined => "\\mathrm{undefined}".to_string(),
+        }
+    }
+
+    /// Writes the value as presentation MathML, with fractions as `<mfrac>`
+    pub fn to_mathml_row(&self) -> String {
+        self.mathml_row().to_string()
+    }
+
+    fn mathml_row(&self) -> MathMl {
+        let (negative, abs) = match self {
+            Value::Exact(v) if v.numer() < 0 => (true, Value::Exact(v.checked_neg().unwrap())),
+            Value::Approx(v) if *v < 0.0 => (true, Value::Approx(-v)),
+            _ => (false, *self),
+        };
+        let abs = match abs {
+            Value::Exact(v) if !v.is_integer() => MathMl::frac(
+                MathMl::Mn(v.numer().to_string()),
+                MathMl::Mn(v.denom().to_string()),
+            ),
+            _ => MathMl::Mn(abs.to_string()),
+        };
+        if negative {
+            MathMl::row([MathMl::mo("−"), abs])
+        } else {
+            abs
+        }
+    }
+
+    fn is_negative(&self) -> bool {
+        match self {
+            Value::Exact(v) => v.numer() < 0,
+            Value::Approx(v) => *v < 0.0,
+            Value::Undefined => false,
+        }
+    }
+
+    fn is_integer(&self) -> bool {
+        matches!(self, Value::Exact(v) if v.is_integer())
+    }
+}
+
+/// Which operands of a step need parentheses.
+///
+/// As in [`BoundOp::format`], negative operands do on the right of an operation or under a
+/// negation, and so do fractions that are raised to a power or have a factorial taken.
+fn step_parens(step: &Step) -> (bool, bool) {
+    match step.op {
+        StepOp::Binary { op: Op::Div, .. } => (false, false),
+        StepOp::Binary { op: Op::Pow, .. } => {
+            (!step.lhs.is_integer() || step.lhs.is_negative(), false)
+        }
+        StepOp::Binary { rhs, .. } => (false, rhs.is_negative()),
+        StepOp::Unary(UnaryOp::Fact) => (!step.lhs.is_integer() || step.lhs.is_negative(), false),
+        StepOp::Unary(UnaryOp::Neg) => (step.lhs.is_negative(), false),
+        StepOp::Unary(_) => (false, false),
+    }
+}
+
+impl Step {
+    /// Writes the step as a LaTeX equation, such as `3 - \frac{8}{3} = \frac{1}{3}`
+    pub fn to_latex(&self) -> String {
+        format!("{} = {}", self.operation_to_latex(), self.result.to_latex())
+    }
+
+    /// Writes the left-hand side of [`Step::to_latex`], such as `3 - \frac{8}{3}`.
+    ///
+    /// Along with the LaTeX of [`Step::result`], this lets steps be aligned on their equals signs.
+    pub fn operation_to_latex(&self) -> String {
+        let (paren_l, paren_r) = step_parens(self);
+        let paren = |v: &Value, paren: bool| {
+            if paren {
+                format!("\\left({}\\right)", v.to_latex())
+            } else {
+                v.to_latex()
+            }
+        };
+        let l = paren(&self.lhs, paren_l);
+        match self.op {
+            StepOp::Binary { op, rhs } => {
+                let r = paren(&rhs, paren_r);
+                match op {
+                    Op::Add => format!("{l} + {r}"),
+                    Op::Sub => format!("{l} - {r}"),
+                    Op::Mul => format!("{l} \\times {r}"),
+                    Op::Div => format!("\\frac{{{l}}}{{{r}}}"),
+                    Op::Pow => format!("{{{l}}}^{{{r}}}"),
+                }
+            }
+            StepOp::Unary(UnaryOp::Fact) => format!("{l}!"),
+            StepOp::Unary(UnaryOp::Sqrt) => format!("\\sqrt{{{l}}}"),
+            StepOp::Unary(UnaryOp::Neg) => format!("-{l}"),
+            StepOp::Unary(UnaryOp::Floor) => format!("\\left\\lfloor {l} \\right\\rfloor"),
+            StepOp::Unary(UnaryOp::Ceil) => format!("\\left\\lceil {l} \\right\\rceil"),
+        }
+    }
+
+    /// Writes the step as a presentation MathML `<math>` element holding an equation
+    pub fn to_mathml(&self) -> String {
+        self.to_mathml_element().to_string()
+    }
+
+    /// The `<math>` element of [`Step::to_mathml`], before it is written as markup
+    pub fn to_mathml_element(&self) -> MathMl {
+        let (paren_l, paren_r) = step_parens(self);
+        let paren = |v: &Value, paren: bool| {
+            if paren {
+                v.mathml_row().parenthesized()
+            } else {
+                v.mathml_row()
+            }
+        };
+        let l = paren(&self.lhs, paren_l);
+        let operation = match self.op {
+            StepOp::Binary { op, rhs } => {
+                let r = paren(&rhs, paren_r);
+                match op {
+                    Op::Add => MathMl::row([l, MathMl::mo("+"), r]),
+                    Op::Sub => MathMl::row([l, MathMl::mo("−"), r]),
+                    Op::Mul => MathMl::row([l, MathMl::mo("×"), r]),
+                    Op::Div => MathMl::frac(l, r),
+                    Op::Pow => MathMl::Msup(Box::new(l), Box::new(r)),
+                }
+            }
+            StepOp::Unary(UnaryOp::Fact) => MathMl::row([l, MathMl::mo("!")]),
+            StepOp::Unary(UnaryOp::Sqrt) => MathMl::Msqrt(Box::new(l)),
+            StepOp::Unary(UnaryOp::Neg) => MathMl::row([MathMl::mo("−"), l]),
+            StepOp::Unary(UnaryOp::Floor) => MathMl::row([MathMl::mo("⌊"), l, MathMl::mo("⌋")]),
+            StepOp::Unary(UnaryOp::Ceil) => MathMl::row([MathMl::mo("⌈"), l, MathMl::mo("⌉")]),
+        };
+        MathMl::Math(Box::new(MathMl::row([
+            operation,
+            MathMl::mo("="),
+            self.result.mathml_row(),
+        ])))
+    }
+}
+
+#[cfg(test)]
+mod render_tests {
+    use crate::BoundOp;
+
+    fn steps_latex(s: &str) -> Vec<String> {
+        let bop = s.parse::<BoundOp>().unwrap();
+        bop.explain().iter().map(|step| step.to_latex()).collect()
+    }
+
+    fn latex(s: &str) -> String {
+        s.parse::<BoundOp>().unwrap().to_latex()
+    }
+
+    fn mathml(s: &str) -> String {
+        s.parse::<BoundOp>().unwrap().to_mathml()
+    }
+
+    #[test]
+    fn test_latex() {
+        assert_eq!(latex("8÷(3-8÷3)"), "\\frac{8}{3 - \\frac{8}{3}}");
+        assert_eq!(
+            latex("(1+(2+3))×4"),
+            "\\left(1 + \\left(2 + 3\\right)\\right) \\times 4"
+        );
+        assert_eq!(latex("(1+2)÷3×4"), "\\frac{1 + 2}{3} \\times 4");
+        assert_eq!(latex("(2+1)^(1+1)"), "{\\left(2 + 1\\right)}^{1 + 1}");
+        assert_eq!(latex("(8÷2)^2^3"), "{\\frac{8}{2}}^{{2}^{3}}");
+        assert_eq!(latex("(2^3)^2"), "{\\left({2}^{3}\\right)}^{2}");
+        assert_eq!(latex("3-(-2)"), "3 - \\left(-2\\right)");
+        assert_eq!(
+            latex("√(1+3)×(3+1)!"),
+            "\\sqrt{1 + 3} \\times \\left(3 + 1\\right)!"
+        );
+        assert_eq!(
+            latex("⌊7÷2⌋"),
+            "\\left\\lfloor \\frac{7}{2} \\right\\rfloor"
+        );
+    }
+
+    #[test]
+    fn test_mathml() {
+        assert_eq!(
+            mathml("8÷(3-8÷3)"),
+            "<math><mfrac><mn>8</mn><mrow><mn>3</mn><mo>−</mo>\
+             <mfrac><mn>8</mn><mn>3</mn></mfrac></mrow></mfrac></math>"
+        );
+        assert_eq!(
+            mathml("(1+2)×2^3"),
+            "<math><mrow><mrow><mo>(</mo><mrow><mn>1</mn><mo>+</mo><mn>2</mn></mrow><mo>)</mo></mrow>\
+             <mo>×</mo><msup><mn>2</mn><mn>3</mn></msup></mrow></math>"
+        );
+        let math = super::MathMl::Mo("<script>&".to_string());
+        assert_eq!(math.to_string(), "<mo>&lt;script&gt;&amp;</mo>");
+        assert_eq!(
+            mathml("-3!"),
+            "<math><mrow><mo>−</mo><mrow><mn>3</mn><mo>!</mo></mrow></mrow></math>"
+        );
+    }
+
+    #[test]
+    fn test_steps_latex() {
+        assert_eq!(
+            steps_latex("8÷(3-8÷3)"),
+            [
+                "\\frac{8}{3} = \\frac{8}{3}",
+                "3 - \\frac{8}{3} = \\frac{1}{3}",
+                "\\frac{8}{\\frac{1}{3}} = 24"
+            ]
+        );
+        assert_eq!(
+            steps_latex("(1-3)×(1÷2)^2"),
+            [
+                "1 - 3 = -2",
+                "\\frac{1}{2} = \\frac{1}{2}",
+                "{\\left(\\frac{1}{2}\\right)}^{2} = \\frac{1}{4}",
+                "-2 \\times \\frac{1}{4} = -\\frac{1}{2}"
+            ]
+        );
+        let step = &"3-8÷3".parse::<BoundOp>().unwrap().explain()[1];
+        assert_eq!(step.operation_to_latex(), "3 - \\frac{8}{3}");
+        assert_eq!(step.result.to_latex(), "\\frac{1}{3}");
+    }
+
+    #[test]
+    fn test_steps_mathml() {
+        let bop = "3-8÷3".parse::<BoundOp>().unwrap();
+        let steps = bop.explain();
+        assert_eq!(
+            steps[1].to_mathml(),
+            "<math><mrow><mrow><mn>3</mn><mo>−</mo><mfrac><mn>8</mn><mn>3</mn></mfrac></mrow>\
+             <mo>=</mo><mfrac><mn>1</mn><mn>3</mn></mfrac></mrow></math>"
+        );
+        let bop = "2×(1-3)".parse::<BoundOp>().unwrap();
+        assert_eq!(
+            bop.explain()[1].to_mathml(),
+            "<math><mrow><mrow><mn>2</mn><mo>×</mo><mrow><mo>(</mo><mrow><mo>−</mo><mn>2</mn></mrow>\
+             <mo>)</mo></mrow></mrow><mo>=</mo><mrow><mo>−</mo><mn>4</mn></mrow></mrow></math>"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_mathml() {
+        let math = "(1+2)÷3".parse::<BoundOp>().unwrap().to_mathml_element();
+        let json = serde_json::to_string(&math).unwrap();
+        assert_eq!(
+            json,
+            r#"{"math":{"mfrac":[{"mrow":[{"mn":"1"},{"mo":"+"},{"mn":"2"}]},{"mn":"3"}]}}"#
+        );
+        assert_eq!(serde_json::from_str::<super::MathMl>(&json).unwrap(), math);
+    }
+}
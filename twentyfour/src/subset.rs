@@ -0,0 +1,647 @@
+use super::{BoundOp, Op, Ops, Rational, UnaryOp};
+use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap},
+    hash::{BuildHasherDefault, Hasher},
+    rc::Rc,
+};
+
+/// A value along with the number of unary operations used to make it
+type Key = (Rational, usize);
+
+/// The expressions that make a key from a sub-multiset, by sub-multiset index and key
+type Trees = HashMap<(usize, Key), Rc<[BoundOp]>>;
+
+/// The distinct values that a sub-multiset of the cards can make, in the order they were found
+#[derive(Debug, Default)]
+struct Table {
+    keys: Vec<Key>,
+    /// The position of each key in `keys`
+    index: HashMap<Key, usize, BuildHasherDefault<KeyHasher>>,
+    /// The positions of the keys in order of value, for looking up approximate values
+    sorted: Vec<(f64, usize)>,
+}
+
+impl Table {
+    fn insert(&mut self, key: Key) {
+        if let Entry::Vacant(entry) = self.index.entry(key) {
+            entry.insert(self.keys.len());
+            self.keys.push(key);
+        }
+    }
+
+    fn position(&self, key: &Key) -> Option<usize> {
+        self.index.get(key).copied()
+    }
+
+    fn sort(&mut self) {
+        self.sorted = self
+            .keys
+            .iter()
+            .enumerate()
+            .map(|(i, (val, _))| (val.to_f64(), i))
+            .collect();
+        self.sorted.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+    }
+
+    /// The positions of the keys whose value is within rounding error of `approx`
+    fn near(&self, approx: f64) -> impl Iterator<Item = usize> + '_ {
+        let tolerance = 1e-9 * approx.abs().max(1.0);
+        let start = self
+            .sorted
+            .partition_point(|(v, _)| *v < approx - tolerance);
+        self.sorted[start..]
+            .iter()
+            .take_while(move |(v, _)| *v <= approx + tolerance)
+            .map(|(_, i)| *i)
+    }
+}
+
+/// A fast hasher for table keys, since they are not attacker-controlled
+#[derive(Default)]
+struct KeyHasher(u64);
+
+impl KeyHasher {
+    fn add(&mut self, n: u64) {
+        self.0 = (self.0.rotate_left(5) ^ n).wrapping_mul(0x517c_c1b7_2722_0a95);
+    }
+}
+
+impl Hasher for KeyHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.add(*b as u64);
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.add(n);
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.add(n as u64);
+    }
+
+    fn write_i128(&mut self, n: i128) {
+        self.add(n as u64);
+        self.add((n >> 64) as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// A memoized search over the sub-multisets of the cards.
+///
+/// Every sub-multiset gets a table of the distinct values it can make, built from the tables of
+/// its two-part splits. Identical cards are interchangeable, so a sub-multiset is identified by
+/// how many of each card value it holds, written as a mixed-radix index.
+///
+/// Tables only hold values. The expressions that make a value are rebuilt on demand by searching
+/// the splits again, which is cheap because most operations can be inverted.
+pub(crate) struct SubsetSearch {
+    /// The distinct card values
+    values: Vec<u8>,
+    /// How many cards there are of each value
+    counts: Vec<usize>,
+    /// The place value of each card value in a sub-multiset index
+    radix: Vec<usize>,
+    ops: Vec<Op>,
+    unary: Vec<UnaryOp>,
+    max_unary: usize,
+    /// Whether every intermediate value must be a positive integer
+    positive_integers: bool,
+    /// Tables for every sub-multiset, indexed by sub-multiset index
+    tables: Vec<Table>,
+    /// Whether the table for the full multiset was built
+    full_built: bool,
+    /// The expressions already rebuilt for each sub-multiset and key, since the same operands
+    /// turn up in many solutions
+    trees: RefCell<Trees>,
+}
+
+impl SubsetSearch {
+    /// Builds the tables for every proper sub-multiset of `cards`.
+    ///
+    /// With `positive_integers`, values that are not positive integers are dropped as soon as
+    /// they are made, so they are never used as operands.
+    pub fn new(cards: &[u8], ops: Ops, positive_integers: bool) -> Self {
+        let mut values = cards.to_vec();
+        values.sort_unstable();
+        values.dedup();
+        let counts = values
+            .iter()
+            .map(|v| cards.iter().filter(|c| *c == v).count())
+            .collect::<Vec<_>>();
+        let mut radix = Vec::with_capacity(values.len());
+        let mut place = 1;
+        for count in &counts {
+            radix.push(place);
+            place *= count + 1;
+        }
+        let unary = ops.unary().to_vec();
+        let max_unary = if unary.is_empty() { 0 } else { ops.max_unary() };
+
+        let mut search = Self {
+            values,
+            counts,
+            radix,
+            ops: ops.into_inner(),
+            unary,
+            max_unary,
+            positive_integers,
+            tables: Vec::with_capacity(place),
+            full_built: false,
+            trees: RefCell::default(),
+        };
+        // A proper sub-multiset always has a smaller index, so its table is ready in time
+        for idx in 0..place - 1 {
+            let table = search.build(idx);
+            search.tables.push(table);
+        }
+        // Most values made from every card are never asked for, so the full multiset is only
+        // tabulated when a unary operation could turn any of them into the target
+        search.full_built = !search.unary.is_empty();
+        let full = if search.full_built {
+            search.build(place - 1)
+        } else {
+            Table::default()
+        };
+        search.tables.push(full);
+        search
+    }
+
+    /// The index of the multiset holding every card
+    pub fn full(&self) -> usize {
+        self.tables.len() - 1
+    }
+
+    /// Every expression over all of the cards that evaluates to `target`
+    pub fn solutions(&self, target: Rational) -> Vec<BoundOp> {
+        self.solutions_in(self.full(), target)
+    }
+
+    /// Every expression over any non-empty sub-multiset of the cards that evaluates to `target`,
+    /// using fewer cards first
+    pub fn subset_solutions(&self, target: Rational) -> Vec<BoundOp> {
+        self.by_size()
+            .flat_map(|idx| self.solutions_in(idx, target))
+            .collect()
+    }
+
+    /// The expressions whose values are nearest to `target`, along with their distance from it.
+    ///
+    /// With `any_subset`, expressions over any non-empty sub-multiset of the cards are considered,
+    /// otherwise only those over all of the cards.
+    pub fn closest(&mut self, target: Rational, any_subset: bool) -> (Rational, Vec<BoundOp>) {
+        if !self.full_built {
+            let full = self.build(self.full());
+            let last = self.tables.len() - 1;
+            self.tables[last] = full;
+            self.full_built = true;
+        }
+        let candidates = if any_subset {
+            self.by_size().collect()
+        } else {
+            vec![self.full()]
+        };
+
+        let distance = |val: Rational| {
+            let diff = val.checked_sub(target)?;
+            if diff < Rational::ZERO {
+                diff.checked_neg()
+            } else {
+                Some(diff)
+            }
+        };
+        let Some(nearest) = candidates
+            .iter()
+            .flat_map(|idx| &self.tables[*idx].keys)
+            .filter_map(|(val, _)| distance(*val))
+            .min()
+        else {
+            return (Rational::ZERO, Vec::new());
+        };
+
+        // The target can be missed from below or above
+        let search = &*self;
+        let mut vals = vec![target.checked_sub(nearest), target.checked_add(nearest)];
+        vals.dedup();
+        let trees = candidates
+            .into_iter()
+            .flat_map(|idx| {
+                vals.iter()
+                    .flatten()
+                    .flat_map(move |val| search.solutions_in(idx, *val))
+            })
+            .collect();
+        (nearest, trees)
+    }
+
+    /// Every expression over the sub-multiset `idx` that evaluates to `target`
+    fn solutions_in(&self, idx: usize, target: Rational) -> Vec<BoundOp> {
+        if self.positive_integers && !is_positive_integer(target) && self.size(idx) > 1 {
+            return Vec::new();
+        }
+        (0..=self.max_unary)
+            .flat_map(|used| self.trees(idx, (target, used)).to_vec())
+            .collect()
+    }
+
+    /// The indices of every non-empty sub-multiset, ordered by size
+    fn by_size(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut idxs = (1..self.tables.len()).collect::<Vec<_>>();
+        idxs.sort_by_key(|idx| self.size(*idx));
+        idxs.into_iter()
+    }
+
+    /// Rebuilds every expression over the sub-multiset `idx` with value and unary count `key`.
+    fn trees(&self, idx: usize, key: Key) -> Rc<[BoundOp]> {
+        if let Some(trees) = self.trees.borrow().get(&(idx, key)) {
+            return trees.clone();
+        }
+        let trees = Rc::<[BoundOp]>::from(self.build_trees(idx, key));
+        self.trees.borrow_mut().insert((idx, key), trees.clone());
+        trees
+    }
+
+    fn build_trees(&self, idx: usize, key: Key) -> Vec<BoundOp> {
+        let (val, used) = key;
+        let mut trees = Vec::new();
+
+        if used == 0 && self.size(idx) == 1 {
+            let card = self.values[self.digits(idx).position(|d| d == 1).unwrap()];
+            if Rational::from(card) == val {
+                trees.push(BoundOp::Val(card));
+            }
+        }
+
+        self.splits(idx, |a, b, same| {
+            self.combine_into(a, b, same, key, |op, l, r| {
+                let rs = self.trees(b, r);
+                for l in self.trees(a, l).iter() {
+                    for r in rs.iter() {
+                        trees.push(BoundOp::BoundOp {
+                            op: *op,
+                            l: Box::new(l.clone()),
+                            r: Box::new(r.clone()),
+                        });
+                    }
+                }
+            })
+        });
+
+        if used > 0 {
+            for &(arg, arg_used) in &self.tables[idx].keys {
+                if arg_used != used - 1 {
+                    continue;
+                }
+                for op in &self.unary {
+                    if op.eval_exact(arg).filter(|v| *v != arg) == Some(val) {
+                        let args = self.trees(idx, (arg, arg_used));
+                        trees.extend(args.iter().map(|arg| BoundOp::Unary {
+                            op: *op,
+                            arg: Box::new(arg.clone()),
+                        }));
+                    }
+                }
+            }
+        }
+        trees
+    }
+
+    /// How many cards of each value the sub-multiset `idx` holds
+    fn digits(&self, idx: usize) -> impl Iterator<Item = usize> + '_ {
+        self.radix
+            .iter()
+            .zip(&self.counts)
+            .map(move |(radix, count)| idx / radix % (count + 1))
+    }
+
+    /// How many cards the sub-multiset `idx` holds
+    fn size(&self, idx: usize) -> usize {
+        self.digits(idx).sum()
+    }
+
+    /// The indices of every sub-multiset of `idx`, including the empty one and `idx` itself
+    fn submultisets(&self, idx: usize) -> Vec<usize> {
+        let mut subs = vec![0];
+        for (digit, radix) in self.digits(idx).zip(&self.radix) {
+            subs = subs
+                .into_iter()
+                .flat_map(|sub| (0..=digit).map(move |d| sub + d * radix))
+                .collect();
+        }
+        subs
+    }
+
+    /// Calls `f` with both orders of every split of `idx` into two non-empty parts, and whether
+    /// both parts are the same multiset. Identical parts are only visited once.
+    fn splits(&self, idx: usize, mut f: impl FnMut(usize, usize, bool)) {
+        for a in self.submultisets(idx) {
+            let b = idx - a;
+            if a == 0 || b == 0 || a > b {
+                continue;
+            }
+            f(a, b, a == b);
+            if a != b {
+                f(b, a, false);
+            }
+        }
+    }
+
+    fn build(&self, idx: usize) -> Table {
+        let mut table = Table::default();
+        match self.size(idx) {
+            0 => return table,
+            1 => {
+                let i = self.digits(idx).position(|d| d == 1).unwrap();
+                table.insert((Rational::from(self.values[i]), 0));
+            }
+            _ => self.splits(idx, |a, b, same| self.combine(&mut table, a, b, same)),
+        }
+
+        // Wrap values in unary operations, including values wrapped here already
+        let mut i = 0;
+        while i < table.keys.len() {
+            let (val, used) = table.keys[i];
+            i += 1;
+            if used >= self.max_unary {
+                continue;
+            }
+            for op in &self.unary {
+                if let Some(new_val) = op.eval_exact(val).filter(|v| *v != val) {
+                    if !self.positive_integers || is_positive_integer(new_val) {
+                        table.insert((new_val, used + 1));
+                    }
+                }
+            }
+        }
+        if self.ops.contains(&Op::Pow) {
+            table.sort();
+        }
+        table
+    }
+
+    /// Records the values made by an operation with a left operand from `a` and a right operand
+    /// from `b`.
+    ///
+    /// Commutative operations are skipped for the mirrored split, and, when `a` and `b` are the
+    /// same multiset, for mirrored operand pairs.
+    fn combine(&self, table: &mut Table, a: usize, b: usize, same: bool) {
+        let mirrored = a > b;
+        for (i, &(lv, lu)) in self.tables[a].keys.iter().enumerate() {
+            for (j, &(rv, ru)) in self.tables[b].keys.iter().enumerate() {
+                if lu + ru > self.max_unary {
+                    continue;
+                }
+                for op in &self.ops {
+                    let commutative = matches!(op, Op::Add | Op::Mul);
+                    if commutative && (mirrored || (same && j < i)) {
+                        continue;
+                    }
+                    if let Some(val) = op.eval_exact(lv, rv) {
+                        if !self.positive_integers || is_positive_integer(val) {
+                            table.insert((val, lu + ru));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Like [`SubsetSearch::combine`], but only looks for ways of making `key`,
+    /// calling `f` with the operation and operands of each one.
+    ///
+    /// Where an operation can be inverted, one operand is looked up instead of searched for.
+    /// The smaller table is iterated, but ways are always reported in the order of the left
+    /// operand, the operation and the right operand.
+    fn combine_into(
+        &self,
+        a: usize,
+        b: usize,
+        same: bool,
+        key: Key,
+        mut f: impl FnMut(&Op, Key, Key),
+    ) {
+        let (lefts, rights) = (&self.tables[a], &self.tables[b]);
+        if !same && rights.keys.len() < lefts.keys.len() {
+            let mut ways = Vec::new();
+            self.combine_into_from_right(a, b, key, |i, k, j| ways.push((i, k, j)));
+            ways.sort_unstable();
+            for (i, k, j) in ways {
+                f(&self.ops[k], lefts.keys[i], rights.keys[j]);
+            }
+            return;
+        }
+
+        let (target, used) = key;
+        let mirrored = a > b;
+        for (i, &(lv, lu)) in lefts.keys.iter().enumerate() {
+            if lu > used {
+                continue;
+            }
+            let ru = used - lu;
+            for op in &self.ops {
+                let commutative = matches!(op, Op::Add | Op::Mul);
+                if commutative && mirrored {
+                    continue;
+                }
+                let mut check = |j: usize, rv: Rational| {
+                    if commutative && same && j < i {
+                        return;
+                    }
+                    if op.eval_exact(lv, rv) == Some(target) {
+                        f(op, (lv, lu), (rv, ru));
+                    }
+                };
+                match right_operand(op, lv, target) {
+                    Operand::Exact(rv) => {
+                        if let Some(j) = rights.position(&(rv, ru)) {
+                            check(j, rv);
+                        }
+                    }
+                    Operand::Near(approx) => {
+                        for j in rights.near(approx) {
+                            let (rv, r_used) = rights.keys[j];
+                            if r_used == ru {
+                                check(j, rv);
+                            }
+                        }
+                    }
+                    Operand::Any => {
+                        for (j, &(rv, r_used)) in rights.keys.iter().enumerate() {
+                            if r_used == ru {
+                                check(j, rv);
+                            }
+                        }
+                    }
+                    Operand::None => {}
+                }
+            }
+        }
+    }
+
+    /// Like [`SubsetSearch::combine_into`] for two different multisets, but iterates the right
+    /// operands and looks up the left ones, calling `f` with the positions of the left operand,
+    /// the operation and the right operand of each way of making `key`.
+    fn combine_into_from_right(
+        &self,
+        a: usize,
+        b: usize,
+        key: Key,
+        mut f: impl FnMut(usize, usize, usize),
+    ) {
+        let (target, used) = key;
+        let mirrored = a > b;
+        let (lefts, rights) = (&self.tables[a], &self.tables[b]);
+        for (j, &(rv, ru)) in rights.keys.iter().enumerate() {
+            if ru > used {
+                continue;
+            }
+            let lu = used - ru;
+            for (k, op) in self.ops.iter().enumerate() {
+                if mirrored && matches!(op, Op::Add | Op::Mul) {
+                    continue;
+                }
+                let mut check = |i: usize, lv: Rational| {
+                    if op.eval_exact(lv, rv) == Some(target) {
+                        f(i, k, j);
+                    }
+                };
+                match left_operands(op, rv, target) {
+                    Some(lvs) => {
+                        for lv in lvs {
+                            if let Some(i) = lefts.position(&(lv, lu)) {
+                                check(i, lv);
+                            }
+                        }
+                    }
+                    None => {
+                        for (i, &(lv, l_used)) in lefts.keys.iter().enumerate() {
+                            if l_used == lu {
+                                check(i, lv);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn is_positive_integer(val: Rational) -> bool {
+    val.is_integer() && val > Rational::ZERO
+}
+
+/// The right operand that could make an operation equal a target
+enum Operand {
+    /// There is no such operand
+    None,
+    /// Only this operand works
+    Exact(Rational),
+    /// Only operands within rounding error of this value can work
+    Near(f64),
+    /// Every operand has to be tried
+    Any,
+}
+
+/// The right operand that makes `op` with left operand `lv` equal `target`
+fn right_operand(op: &Op, lv: Rational, target: Rational) -> Operand {
+    let exact = |rv: Option<Rational>| rv.map_or(Operand::None, Operand::Exact);
+    match op {
+        Op::Add => exact(target.checked_sub(lv)),
+        Op::Sub => exact(lv.checked_sub(target)),
+        Op::Mul if lv != Rational::ZERO => exact(target.checked_div(lv)),
+        Op::Div if target != Rational::ZERO => exact(lv.checked_div(target)),
+        // 0, 1 and -1 reach the same value with many exponents
+        Op::Pow if lv.denom() != 1 || lv.numer().abs() > 1 => {
+            if target == Rational::ZERO {
+                Operand::None
+            } else {
+                Operand::Near(target.to_f64().abs().ln() / lv.to_f64().abs().ln())
+            }
+        }
+        _ => Operand::Any,
+    }
+}
+
+/// The left operands that could make `op` with right operand `rv` equal `target`, or `None`
+/// if every operand has to be tried
+fn left_operands(op: &Op, rv: Rational, target: Rational) -> Option<Vec<Rational>> {
+    let exact = |lv: Option<Rational>| Some(lv.into_iter().collect());
+    match op {
+        Op::Add => exact(target.checked_sub(rv)),
+        Op::Sub => exact(target.checked_add(rv)),
+        Op::Mul if rv == Rational::ZERO => (target != Rational::ZERO).then(Vec::new),
+        Op::Mul => exact(target.checked_div(rv)),
+        Op::Div if rv == Rational::ZERO => Some(Vec::new()),
+        Op::Div => exact(target.checked_mul(rv)),
+        // Every base raised to 0 is 1
+        Op::Pow if rv == Rational::ZERO => (target != Rational::ONE).then(Vec::new),
+        Op::Pow => {
+            // `lv^(p/q)` is the `q`th root of `lv` raised to `p`, so that root is a `p`th root of
+            // the target. Both signs of an even root have to be tried.
+            let (p, q) = (rv.numer(), Rational::from_integer(rv.denom()));
+            let Some(root) = Rational::new(1, p).and_then(|exp| target.checked_pow(exp)) else {
+                return Some(Vec::new());
+            };
+            let mut roots = vec![root];
+            if p % 2 == 0 {
+                roots.extend(root.checked_neg());
+            }
+            let mut lvs = roots
+                .into_iter()
+                .filter_map(|root| root.checked_pow(q))
+                .collect::<Vec<_>>();
+            lvs.dedup();
+            Some(lvs)
+        }
+    }
+}
+
+#[cfg(test)]
+mod subset_tests {
+    use super::*;
+
+    fn search(cards: &[u8]) -> SubsetSearch {
+        SubsetSearch::new(
+            cards,
+            Ops::with_ops(vec![Op::Add, Op::Sub, Op::Mul, Op::Div]),
+            false,
+        )
+    }
+
+    #[test]
+    fn test_submultisets() {
+        let search = search(&[1, 1, 2]);
+        assert_eq!(search.full(), 5);
+        assert_eq!(search.submultisets(search.full()).len(), 6);
+        assert_eq!(search.submultisets(1), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_solutions() {
+        let search = search(&[1, 1, 2]);
+        let sols = search
+            .solutions(Rational::from(4))
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(sols, vec!["(1+(1+2))", "((1+1)+2)", "((1+1)×2)"]);
+    }
+
+    #[test]
+    fn test_closest() {
+        let mut search = search(&[2, 3]);
+        let (distance, sols) = search.closest(Rational::from(8), false);
+        assert_eq!(distance, Rational::from(2));
+        let sols = sols.into_iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(sols, vec!["(2×3)"]);
+
+        let (distance, sols) = search.closest(Rational::from(3), true);
+        assert_eq!(distance, Rational::ZERO);
+        assert_eq!(sols.len(), 1);
+    }
+}